@@ -0,0 +1,196 @@
+//! Known-differences baseline.
+//!
+//! A baseline file lists accepted diffs, keyed by endpoint, normalized JSON path
+//! (array indices replaced by `[*]`) and `DiffType`. A mismatch made up only of
+//! baselined diffs is counted as "known" instead of "mismatched", so regular
+//! runs only surface new differences. Entries that are no longer seen during a
+//! scan are reported as stale, and entries past their expiry date stop
+//! suppressing anything.
+//!
+//! Example `baseline.json`:
+//!
+//! ```json
+//! {
+//!   "entries": [
+//!     {
+//!       "endpoint": "block",
+//!       "path": "extrinsics[*].info.weight",
+//!       "diff_type": "ValueMismatch",
+//!       "reason": "Sidecar still reports V1 weights",
+//!       "expires": "2026-12-31",
+//!       "issue": "https://github.com/paritytech/polkadot-rest-api/issues/123"
+//!     }
+//!   ]
+//! }
+//! ```
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::diff::{DiffType, JsonDiff};
+use crate::endpoints::EndpointType;
+
+/// A single accepted difference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// Endpoint name (e.g. "block", "pallet-storage")
+    pub endpoint: String,
+    /// Normalized diff path (e.g. "extrinsics[*].info.weight")
+    pub path: String,
+    /// Type of difference being accepted
+    pub diff_type: DiffType,
+    /// Why this difference is accepted
+    pub reason: String,
+    /// Date (YYYY-MM-DD) after which the entry no longer applies
+    #[serde(default)]
+    pub expires: Option<String>,
+    /// Tracking issue for the underlying difference
+    #[serde(default)]
+    pub issue: Option<String>,
+}
+
+impl BaselineEntry {
+    /// Whether this entry covers the given diff
+    fn covers(&self, diff: &JsonDiff) -> bool {
+        self.diff_type == diff.diff_type && self.path == diff.normalized_path()
+    }
+}
+
+impl fmt::Display for BaselineEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?} ({})", self.path, self.diff_type, self.reason)?;
+        if let Some(ref issue) = self.issue {
+            write!(f, " [{}]", issue)?;
+        }
+        if let Some(ref expires) = self.expires {
+            write!(f, " expires {}", expires)?;
+        }
+        Ok(())
+    }
+}
+
+/// Root baseline file structure, persisted as JSON
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BaselineFile {
+    #[serde(default)]
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl BaselineFile {
+    /// Load and validate a baseline file
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read baseline file {}: {}", path.display(), e))?;
+        let data: BaselineFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid baseline file {}: {}", path.display(), e))?;
+
+        for entry in &data.entries {
+            entry.endpoint.parse::<EndpointType>()?;
+            if let Some(ref expires) = entry.expires {
+                NaiveDate::parse_from_str(expires, "%Y-%m-%d").map_err(|_| {
+                    format!(
+                        "Invalid expiry date '{}' for baseline entry {} (expected YYYY-MM-DD)",
+                        expires, entry.path
+                    )
+                })?;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Select the entries for one endpoint, splitting off the expired ones
+    pub fn for_endpoint(&self, endpoint: &EndpointType) -> Baseline {
+        let today = chrono::Local::now().date_naive();
+        let mut active = Vec::new();
+        let mut expired = Vec::new();
+
+        for entry in &self.entries {
+            let is_endpoint = entry
+                .endpoint
+                .parse::<EndpointType>()
+                .map(|e| e == *endpoint)
+                .unwrap_or(false);
+            if !is_endpoint {
+                continue;
+            }
+            let is_expired = entry
+                .expires
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .map(|d| d < today)
+                .unwrap_or(false);
+            if is_expired {
+                expired.push(entry.clone());
+            } else {
+                active.push(entry.clone());
+            }
+        }
+
+        let seen = vec![false; active.len()];
+        Baseline {
+            entries: active,
+            expired,
+            seen,
+        }
+    }
+}
+
+/// Baseline entries active for a single scan, with suppression accounting
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    entries: Vec<BaselineEntry>,
+    expired: Vec<BaselineEntry>,
+    /// Whether each entry has been seen during this scan
+    seen: Vec<bool>,
+}
+
+impl Baseline {
+    /// Number of active (non-expired) entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether every non-equivalent diff is baselined, marking the matching
+    /// entries as seen. Returns false when there is no such diff.
+    pub fn covers_all(&mut self, diffs: &[JsonDiff]) -> bool {
+        let hard_diffs: Vec<&JsonDiff> = diffs
+            .iter()
+            .filter(|d| !d.diff_type.is_equivalent())
+            .collect();
+        if self.entries.is_empty() || hard_diffs.is_empty() {
+            return false;
+        }
+
+        let mut all_covered = true;
+        for diff in hard_diffs {
+            let mut covered = false;
+            for (entry, seen) in self.entries.iter().zip(self.seen.iter_mut()) {
+                if entry.covers(diff) {
+                    *seen = true;
+                    covered = true;
+                }
+            }
+            all_covered &= covered;
+        }
+        all_covered
+    }
+
+    /// Entries that were never matched during this scan
+    pub fn stale(&self) -> Vec<&BaselineEntry> {
+        self.entries
+            .iter()
+            .zip(self.seen.iter())
+            .filter(|(_, seen)| !**seen)
+            .map(|(entry, _)| entry)
+            .collect()
+    }
+
+    /// Entries past their expiry date (no longer suppressing diffs)
+    pub fn expired(&self) -> &[BaselineEntry] {
+        &self.expired
+    }
+}
@@ -0,0 +1,272 @@
+//! Record-and-replay cassette of raw responses.
+//!
+//! With `--record`, the final attempt of every request (status, headers, body and
+//! timing, or the transport failure) is appended to a gzip-compressed JSON Lines
+//! file keyed by URL. With `--replay`, the same scan runs against that file with
+//! no servers running: every request is answered from the cassette, so rules or
+//! diff changes can be re-evaluated over a large scan in seconds.
+//!
+//! Each recording session appends a new gzip member, so one cassette can hold
+//! several scans. When a URL was recorded more than once, the latest entry wins.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::http::{ErrorKind, HeaderList, RawResponse};
+
+/// One recorded request
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+/// A received response in serializable form (also used by the Sidecar cache)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: HeaderList,
+    /// Body text (invalid UTF-8 is replaced)
+    pub body: String,
+    pub elapsed_ms: f64,
+}
+
+impl StoredResponse {
+    pub fn new(raw: &RawResponse) -> Self {
+        StoredResponse {
+            status: raw.status,
+            headers: raw.headers.clone(),
+            body: String::from_utf8_lossy(&raw.body).into_owned(),
+            elapsed_ms: raw.elapsed.as_secs_f64() * 1000.0,
+        }
+    }
+
+    pub fn to_raw(&self) -> RawResponse {
+        RawResponse {
+            status: self.status,
+            headers: self.headers.clone(),
+            body: self.body.as_bytes().to_vec(),
+            elapsed: Duration::from_secs_f64(self.elapsed_ms.max(0.0) / 1000.0),
+        }
+    }
+}
+
+/// What the final attempt of a request returned
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Outcome {
+    Response(StoredResponse),
+    /// Transport failure: "connect", "timeout" or "request"
+    Failure {
+        kind: String,
+        message: String,
+    },
+}
+
+impl Outcome {
+    fn from_attempt(attempt: &Result<RawResponse, ErrorKind>) -> Self {
+        match attempt {
+            Ok(raw) => Outcome::Response(StoredResponse::new(raw)),
+            Err(kind) => Outcome::Failure {
+                kind: kind.name().to_string(),
+                message: match kind {
+                    ErrorKind::Connect(message)
+                    | ErrorKind::Timeout(message)
+                    | ErrorKind::Request(message) => message.clone(),
+                    _ => String::new(),
+                },
+            },
+        }
+    }
+
+    fn to_attempt(&self) -> Result<RawResponse, ErrorKind> {
+        match self {
+            Outcome::Response(response) => Ok(response.to_raw()),
+            Outcome::Failure { kind, message } => Err(match kind.as_str() {
+                "connect" => ErrorKind::Connect(message.clone()),
+                "timeout" => ErrorKind::Timeout(message.clone()),
+                _ => ErrorKind::Request(message.clone()),
+            }),
+        }
+    }
+}
+
+/// A cassette opened for recording or replaying
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    /// Replay: recorded outcomes by URL
+    entries: HashMap<String, Outcome>,
+    /// Record: gzip member appended to the cassette (None once finished or after a write error)
+    writer: Option<Mutex<Option<GzEncoder<BufWriter<File>>>>>,
+    /// Requests recorded, or answered from the cassette
+    hits: AtomicU64,
+    /// Replay: requests whose URL was not recorded
+    misses: AtomicU64,
+}
+
+impl Cassette {
+    /// Open a cassette for recording (appending to an existing one)
+    pub fn record(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open cassette {}: {}", path.display(), e))?;
+        let encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        Ok(Cassette {
+            path: path.to_path_buf(),
+            entries: HashMap::new(),
+            writer: Some(Mutex::new(Some(encoder))),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// Load a recorded cassette for replay
+    pub fn replay(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(path)
+            .map_err(|e| format!("Failed to read cassette {}: {}", path.display(), e))?;
+        let mut entries = HashMap::new();
+        for (i, line) in BufReader::new(MultiGzDecoder::new(file))
+            .lines()
+            .enumerate()
+        {
+            // A recording cut short leaves a truncated last member; keep what was read
+            let Ok(line) = line else {
+                eprintln!(
+                    "Warning: cassette {} is truncated after {} entries",
+                    path.display(),
+                    i
+                );
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry = serde_json::from_str(&line).map_err(|e| {
+                format!(
+                    "Invalid cassette entry {} in {}: {}",
+                    i + 1,
+                    path.display(),
+                    e
+                )
+            })?;
+            entries.insert(entry.url, entry.outcome);
+        }
+        Ok(Cassette {
+            path: path.to_path_buf(),
+            entries,
+            writer: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.writer.is_none()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Recorded URLs (replay)
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Replay the recorded outcome of a URL; a URL that was not recorded fails as a request error
+    pub fn replay_attempt(&self, url: &str) -> Result<RawResponse, ErrorKind> {
+        match self.entries.get(url) {
+            Some(outcome) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                outcome.to_attempt()
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Err(ErrorKind::Request(format!(
+                    "not recorded in cassette: {}",
+                    url
+                )))
+            }
+        }
+    }
+
+    /// Append the final attempt of a request. A write error stops the recording
+    /// with a warning instead of failing the scan.
+    pub fn record_attempt(&self, url: &str, attempt: &Result<RawResponse, ErrorKind>) {
+        let Some(ref writer) = self.writer else {
+            return;
+        };
+        let entry = Entry {
+            url: url.to_string(),
+            outcome: Outcome::from_attempt(attempt),
+        };
+        let mut writer = writer.lock().unwrap();
+        let Some(encoder) = writer.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *encoder, &entry)
+            .map_err(std::io::Error::from)
+            .and_then(|_| encoder.write_all(b"\n"));
+        match written {
+            Ok(()) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                eprintln!(
+                    "Warning: failed to write cassette {}: {} (recording stopped)",
+                    self.path.display(),
+                    e
+                );
+                *writer = None;
+            }
+        }
+    }
+
+    /// Complete the recorded gzip member; without this the last entries are lost
+    pub fn finish(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(ref writer) = self.writer else {
+            return Ok(());
+        };
+        if let Some(encoder) = writer.lock().unwrap().take() {
+            encoder
+                .finish()
+                .and_then(|mut file| file.flush())
+                .map_err(|e| format!("Failed to write cassette {}: {}", self.path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    /// One-line summary, e.g. "recorded 1200 responses" or "replayed 1200 responses, 3 not recorded"
+    pub fn summary(&self) -> String {
+        let hits = self.hits.load(Ordering::Relaxed);
+        if !self.is_replay() {
+            return format!("recorded {} responses to {}", hits, self.path.display());
+        }
+        let misses = self.misses.load(Ordering::Relaxed);
+        let mut summary = format!("replayed {} responses from {}", hits, self.path.display());
+        if misses > 0 {
+            summary.push_str(&format!(
+                ", {} not recorded (run with the same URLs and scan options as the recording)",
+                misses
+            ));
+        }
+        summary
+    }
+}
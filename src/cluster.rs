@@ -0,0 +1,253 @@
+//! Diff fingerprinting and clustering across blocks.
+//!
+//! One serializer bug usually produces the same diff in every block of a scan.
+//! Each `JsonDiff` is fingerprinted by its normalized path (array indices as `[*]`),
+//! its `DiffType` and the pair of value types, and mismatches are grouped by
+//! fingerprint so reports can show a handful of root causes instead of thousands
+//! of individual issues. Equivalent diffs (values only equal after a normalization
+//! or in another encoding) are clustered separately, so reports show where
+//! leniency is in effect. The grouping itself (`GroupSet`) is shared with the
+//! header and schema checks.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::diff::{DiffType, JsonDiff, NumericDelta};
+use crate::rules::Normalization;
+
+/// Maximum number of example URLs kept per group
+const MAX_EXAMPLE_URLS: usize = 3;
+
+/// What the members of a `GroupSet` group have in common
+pub trait GroupKey {
+    /// Orders groups with the same occurrence count and first block
+    fn label(&self) -> &str;
+
+    /// Fold another member of the same group into this key (e.g. to count it)
+    fn merge(&mut self, _other: Self)
+    where
+        Self: Sized,
+    {
+    }
+}
+
+/// All occurrences of one fingerprint over a scan
+#[derive(Debug, Clone)]
+pub struct Group<K> {
+    pub key: K,
+    /// Number of results containing this fingerprint
+    pub occurrences: u32,
+    pub first_block: u64,
+    pub last_block: u64,
+    /// First few Rust API URLs where the fingerprint was seen
+    pub example_urls: Vec<String>,
+}
+
+impl<K: fmt::Display> fmt::Display for Group<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.key.fmt(f)
+    }
+}
+
+/// Groups keyed by fingerprint
+#[derive(Debug, Clone)]
+pub struct GroupSet<K> {
+    groups: Vec<Group<K>>,
+    /// Fingerprint -> index into `groups`
+    index: HashMap<String, usize>,
+}
+
+impl<K> Default for GroupSet<K> {
+    fn default() -> Self {
+        GroupSet {
+            groups: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<K: GroupKey> GroupSet<K> {
+    /// Add the `(fingerprint, key)` members of one result. A result counts once
+    /// per group, however many of its members fall into it.
+    pub fn add(&mut self, block: u64, url: &str, members: impl IntoIterator<Item = (String, K)>) {
+        let mut seen_in_result: Vec<usize> = Vec::new();
+
+        for (fingerprint, key) in members {
+            let idx = match self.index.get(&fingerprint) {
+                Some(&idx) => {
+                    self.groups[idx].key.merge(key);
+                    idx
+                }
+                None => {
+                    self.groups.push(Group {
+                        key,
+                        occurrences: 0,
+                        first_block: block,
+                        last_block: block,
+                        example_urls: Vec::new(),
+                    });
+                    self.index.insert(fingerprint, self.groups.len() - 1);
+                    self.groups.len() - 1
+                }
+            };
+
+            if seen_in_result.contains(&idx) {
+                continue;
+            }
+            seen_in_result.push(idx);
+            let group = &mut self.groups[idx];
+            group.occurrences += 1;
+            group.first_block = group.first_block.min(block);
+            group.last_block = group.last_block.max(block);
+            if group.example_urls.len() < MAX_EXAMPLE_URLS {
+                group.example_urls.push(url.to_string());
+            }
+        }
+    }
+
+    /// Whether no group was added
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// All groups, in the order they were first seen
+    pub fn iter(&self) -> impl Iterator<Item = &Group<K>> {
+        self.groups.iter()
+    }
+
+    /// Groups ordered by occurrence count (most frequent first)
+    pub fn sorted(&self) -> Vec<&Group<K>> {
+        let mut sorted: Vec<&Group<K>> = self.groups.iter().collect();
+        sorted.sort_by(|a, b| {
+            b.occurrences
+                .cmp(&a.occurrences)
+                .then_with(|| a.first_block.cmp(&b.first_block))
+                .then_with(|| a.key.label().cmp(b.key.label()))
+        });
+        sorted
+    }
+}
+
+/// What the diffs of one cluster have in common
+#[derive(Debug, Clone)]
+pub struct DiffSignature {
+    /// Normalized diff path (e.g. "extrinsics[*].info.weight")
+    pub path: String,
+    pub diff_type: DiffType,
+    /// Normalization that made the values equal (for `DiffType::Normalized`)
+    pub normalization: Option<Normalization>,
+    /// Value type on the Rust side ("missing" when the value is absent)
+    pub rust_type: &'static str,
+    /// Value type on the Sidecar side ("missing" when the value is absent)
+    pub sidecar_type: &'static str,
+    /// Total number of individual diffs with this fingerprint
+    pub diff_count: u32,
+    /// Largest numeric difference seen (for paths with a tolerance rule)
+    pub max_delta: Option<NumericDelta>,
+}
+
+impl DiffSignature {
+    fn new(diff: &JsonDiff) -> Self {
+        let (rust_type, sidecar_type) = diff.value_types();
+        DiffSignature {
+            path: diff.normalized_path(),
+            diff_type: diff.diff_type,
+            normalization: diff.normalization,
+            rust_type,
+            sidecar_type,
+            diff_count: 1,
+            max_delta: diff.delta,
+        }
+    }
+}
+
+impl GroupKey for DiffSignature {
+    fn label(&self) -> &str {
+        &self.path
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.diff_count += other.diff_count;
+        if let Some(delta) = other.max_delta {
+            if self.max_delta.is_none_or(|max| delta.delta > max.delta) {
+                self.max_delta = Some(delta);
+            }
+        }
+    }
+}
+
+impl fmt::Display for DiffSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?}", self.path, self.diff_type)?;
+        if let Some(normalization) = self.normalization {
+            write!(f, " by {}", normalization)?;
+        }
+        write!(
+            f,
+            " (rust={} vs sidecar={})",
+            self.rust_type, self.sidecar_type
+        )?;
+        if let Some(delta) = self.max_delta {
+            write!(f, ", max {}", delta)?;
+        }
+        Ok(())
+    }
+}
+
+/// All occurrences of one diff fingerprint
+pub type DiffCluster = Group<DiffSignature>;
+
+/// Clusters keyed by fingerprint
+type ClusterSet = GroupSet<DiffSignature>;
+
+/// Diff clusters collected over one scan
+#[derive(Debug, Clone, Default)]
+pub struct DiffClusters {
+    mismatches: ClusterSet,
+    equivalent: ClusterSet,
+}
+
+impl DiffClusters {
+    /// Add the diffs of one result: real diffs go to the mismatch clusters,
+    /// equivalent diffs to the equivalence clusters
+    pub fn add(&mut self, block: u64, url: &str, diffs: &[JsonDiff]) {
+        let members = |equivalent: bool| {
+            diffs
+                .iter()
+                .filter(move |d| d.diff_type.is_equivalent() == equivalent)
+                .map(|d| (d.fingerprint(), DiffSignature::new(d)))
+        };
+        self.mismatches.add(block, url, members(false));
+        self.equivalent.add(block, url, members(true));
+    }
+
+    /// Whether no mismatch was clustered
+    pub fn is_empty(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Total diff count per diff type, over mismatches and equivalences
+    /// (e.g. NullVsMissing separately from MissingInRust)
+    pub fn totals_by_type(&self) -> Vec<(DiffType, u32)> {
+        let mut totals: Vec<(DiffType, u32)> = Vec::new();
+        for cluster in self.mismatches.iter().chain(self.equivalent.iter()) {
+            let signature = &cluster.key;
+            match totals.iter_mut().find(|(t, _)| *t == signature.diff_type) {
+                Some((_, count)) => *count += signature.diff_count,
+                None => totals.push((signature.diff_type, signature.diff_count)),
+            }
+        }
+        totals.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        totals
+    }
+
+    /// Mismatch clusters ordered by occurrence count (most frequent first)
+    pub fn sorted(&self) -> Vec<&DiffCluster> {
+        self.mismatches.sorted()
+    }
+
+    /// Equivalence clusters (normalized / encoding-only values), most frequent first
+    pub fn sorted_equivalent(&self) -> Vec<&DiffCluster> {
+        self.equivalent.sorted()
+    }
+}
@@ -4,10 +4,13 @@
 //! across multiple runs of the checker.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
+use crate::http::ErrorCounts;
+use crate::latency::LatencyComparison;
+
 /// Merge overlapping or adjacent block ranges in-place.
 fn merge_ranges(ranges: &mut Vec<(u32, u32)>) {
     if ranges.is_empty() {
@@ -67,12 +70,24 @@ pub struct PalletCoverage {
     pub matched: u32,
     /// Number of mismatched comparisons
     pub mismatched: u32,
+    /// Number of mismatches made up only of baselined (known) diffs
+    #[serde(default)]
+    pub known: u32,
     /// Number of Rust API errors
     pub rust_errors: u32,
     /// Number of Sidecar API errors
     pub sidecar_errors: u32,
     /// Number of errors from both APIs
     pub both_errors: u32,
+    /// Number of same-status errors whose bodies differ (with `--error-parity`)
+    #[serde(default)]
+    pub error_body_mismatches: u32,
+    /// Number of blocks the servers disagree on (with `--verify-block-hashes`)
+    #[serde(default)]
+    pub desyncs: u32,
+    /// Rust vs Sidecar latency in the latest run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyComparison>,
     /// Last tested timestamp
     pub last_tested: String,
 }
@@ -85,9 +100,13 @@ impl PalletCoverage {
             total_blocks_tested: 0,
             matched: 0,
             mismatched: 0,
+            known: 0,
             rust_errors: 0,
             sidecar_errors: 0,
             both_errors: 0,
+            error_body_mismatches: 0,
+            desyncs: 0,
+            latency: None,
             last_tested: String::new(),
         }
     }
@@ -99,9 +118,12 @@ impl PalletCoverage {
         end_block: u32,
         matched: u32,
         mismatched: u32,
+        known: u32,
         rust_errors: u32,
         sidecar_errors: u32,
         both_errors: u32,
+        error_body_mismatches: u32,
+        desyncs: u32,
     ) {
         self.block_ranges.push((start_block, end_block));
         merge_ranges(&mut self.block_ranges);
@@ -110,15 +132,25 @@ impl PalletCoverage {
         self.total_blocks_tested += blocks_in_run;
         self.matched += matched;
         self.mismatched += mismatched;
+        self.known += known;
         self.rust_errors += rust_errors;
         self.sidecar_errors += sidecar_errors;
         self.both_errors += both_errors;
+        self.error_body_mismatches += error_body_mismatches;
+        self.desyncs += desyncs;
 
         self.last_tested = chrono::Utc::now().to_rfc3339();
     }
 
     fn total_tests(&self) -> u32 {
-        self.matched + self.mismatched + self.rust_errors + self.sidecar_errors + self.both_errors
+        self.matched
+            + self.mismatched
+            + self.known
+            + self.rust_errors
+            + self.sidecar_errors
+            + self.both_errors
+            + self.error_body_mismatches
+            + self.desyncs
     }
 
     /// Get pass rate as percentage
@@ -139,9 +171,30 @@ pub struct EndpointCoverage {
     /// Stats for non-pallet endpoints
     pub matched: u32,
     pub mismatched: u32,
+    #[serde(default)]
+    pub known: u32,
     pub rust_errors: u32,
     pub sidecar_errors: u32,
     pub both_errors: u32,
+    #[serde(default)]
+    pub error_body_mismatches: u32,
+    #[serde(default)]
+    pub desyncs: u32,
+    /// Baseline entries not seen during the last run (stale)
+    #[serde(default)]
+    pub baseline_stale: u32,
+    /// Retries of transient request failures, over all runs (not counted as results)
+    #[serde(default)]
+    pub retries: u32,
+    /// Errors by side, kind and status (e.g. "rust http 500"), over all runs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors_by_kind: BTreeMap<String, u32>,
+    /// Rust vs Sidecar latency in the latest run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyComparison>,
+    /// Rust vs Sidecar latency per account label in the latest run (account endpoints)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub account_latency: BTreeMap<String, LatencyComparison>,
     /// Whether this endpoint has been tested at all
     pub tested: bool,
     /// Last tested timestamp
@@ -160,9 +213,17 @@ impl EndpointCoverage {
             block_ranges: Vec::new(),
             matched: 0,
             mismatched: 0,
+            known: 0,
             rust_errors: 0,
             sidecar_errors: 0,
             both_errors: 0,
+            error_body_mismatches: 0,
+            desyncs: 0,
+            baseline_stale: 0,
+            retries: 0,
+            errors_by_kind: BTreeMap::new(),
+            latency: None,
+            account_latency: BTreeMap::new(),
             tested: false,
             last_tested: String::new(),
         }
@@ -176,9 +237,12 @@ impl EndpointCoverage {
         end_block: u32,
         matched: u32,
         mismatched: u32,
+        known: u32,
         rust_errors: u32,
         sidecar_errors: u32,
         both_errors: u32,
+        error_body_mismatches: u32,
+        desyncs: u32,
     ) {
         self.tested = true;
         self.last_tested = chrono::Utc::now().to_rfc3339();
@@ -192,9 +256,12 @@ impl EndpointCoverage {
                 end_block,
                 matched,
                 mismatched,
+                known,
                 rust_errors,
                 sidecar_errors,
                 both_errors,
+                error_body_mismatches,
+                desyncs,
             );
         }
     }
@@ -206,9 +273,12 @@ impl EndpointCoverage {
         end_block: u32,
         matched: u32,
         mismatched: u32,
+        known: u32,
         rust_errors: u32,
         sidecar_errors: u32,
         both_errors: u32,
+        error_body_mismatches: u32,
+        desyncs: u32,
     ) {
         self.tested = true;
         self.last_tested = chrono::Utc::now().to_rfc3339();
@@ -218,9 +288,12 @@ impl EndpointCoverage {
 
         self.matched += matched;
         self.mismatched += mismatched;
+        self.known += known;
         self.rust_errors += rust_errors;
         self.sidecar_errors += sidecar_errors;
         self.both_errors += both_errors;
+        self.error_body_mismatches += error_body_mismatches;
+        self.desyncs += desyncs;
     }
 
     /// Add account endpoint coverage result (delegates to add_block_run)
@@ -231,18 +304,24 @@ impl EndpointCoverage {
         end_block: u32,
         matched: u32,
         mismatched: u32,
+        known: u32,
         rust_errors: u32,
         sidecar_errors: u32,
         both_errors: u32,
+        error_body_mismatches: u32,
+        desyncs: u32,
     ) {
         self.add_block_run(
             start_block,
             end_block,
             matched,
             mismatched,
+            known,
             rust_errors,
             sidecar_errors,
             both_errors,
+            error_body_mismatches,
+            desyncs,
         );
     }
 
@@ -260,8 +339,82 @@ impl EndpointCoverage {
         }
     }
 
+    /// Add a runtime endpoint result whose error bodies differ (same status)
+    pub fn add_error_body_run(&mut self) {
+        self.tested = true;
+        self.last_tested = chrono::Utc::now().to_rfc3339();
+        self.error_body_mismatches += 1;
+    }
+
+    /// Add a runtime endpoint result whose mismatch was fully baselined
+    pub fn add_known_run(&mut self) {
+        self.tested = true;
+        self.last_tested = chrono::Utc::now().to_rfc3339();
+        self.known += 1;
+    }
+
+    /// Record how many baseline entries went unseen in the latest run
+    pub fn set_baseline_stale(&mut self, stale: usize) {
+        self.baseline_stale = stale as u32;
+    }
+
+    /// Add the retries of transient request failures from a run
+    pub fn add_retries(&mut self, retries: u32) {
+        self.retries += retries;
+    }
+
+    /// Add the error breakdown of a run
+    pub fn add_error_counts(&mut self, errors: &ErrorCounts) {
+        for (side, kind, status, count) in errors.iter() {
+            let key = match status {
+                Some(status) => format!("{} {} {}", side, kind, status),
+                None => format!("{} {}", side, kind),
+            };
+            *self.errors_by_kind.entry(key).or_default() += count;
+        }
+    }
+
+    /// Record the latency of the latest run
+    pub fn set_latency(&mut self, latency: LatencyComparison) {
+        if !latency.is_empty() {
+            self.latency = Some(latency);
+        }
+    }
+
+    /// Record the latency of one pallet in the latest run
+    pub fn set_pallet_latency(&mut self, pallet: &str, latency: LatencyComparison) {
+        if let Some(coverage) = self.pallets.as_mut().and_then(|p| p.get_mut(pallet)) {
+            if !latency.is_empty() {
+                coverage.latency = Some(latency);
+            }
+        }
+    }
+
+    /// Record the latency of one account in the latest run
+    pub fn set_account_latency(&mut self, account: &str, latency: LatencyComparison) {
+        if !latency.is_empty() {
+            self.account_latency.insert(account.to_string(), latency);
+        }
+    }
+
+    /// Error breakdown as one line, e.g. "rust http 500 x3, sidecar timeout x1"
+    fn errors_by_kind_summary(&self) -> String {
+        self.errors_by_kind
+            .iter()
+            .map(|(key, count)| format!("{} x{}", key, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     fn total_tests(&self) -> u32 {
-        self.matched + self.mismatched + self.rust_errors + self.sidecar_errors + self.both_errors
+        self.matched
+            + self.mismatched
+            + self.known
+            + self.rust_errors
+            + self.sidecar_errors
+            + self.both_errors
+            + self.error_body_mismatches
+            + self.desyncs
     }
 
     /// Whether this endpoint has any issues (mismatches or errors)
@@ -270,6 +423,13 @@ impl EndpointCoverage {
             || self.rust_errors > 0
             || self.sidecar_errors > 0
             || self.both_errors > 0
+            || self.error_body_mismatches > 0
+            || self.desyncs > 0
+    }
+
+    /// Whether this endpoint has known (baselined) mismatches or stale baseline entries
+    pub fn has_baseline_activity(&self) -> bool {
+        self.known > 0 || self.baseline_stale > 0
     }
 
     /// Get pass rate
@@ -684,7 +844,7 @@ impl CoverageData {
                     report.push_str(&format!("| {} | ❌ | - | - | - |\n", endpoint));
                 }
             }
-            report.push_str("\n");
+            report.push('\n');
 
             // Block endpoints table
             report.push_str("#### Block Endpoints\n\n");
@@ -693,7 +853,7 @@ impl CoverageData {
             for endpoint in &block_endpoints {
                 Self::write_md_block_row(&mut report, chain, endpoint, details_filename);
             }
-            report.push_str("\n");
+            report.push('\n');
 
             // Account endpoints table
             report.push_str("#### Account Endpoints\n\n");
@@ -702,7 +862,7 @@ impl CoverageData {
             for endpoint in &account_endpoints {
                 Self::write_md_block_row(&mut report, chain, endpoint, details_filename);
             }
-            report.push_str("\n");
+            report.push('\n');
 
             // Standalone endpoints table
             report.push_str("#### Standalone Endpoints\n\n");
@@ -720,7 +880,27 @@ impl CoverageData {
                     report.push_str(&format!("| {} | ❌ | - |\n", endpoint));
                 }
             }
-            report.push_str("\n");
+            report.push('\n');
+
+            // Latency of the latest run per endpoint
+            let latencies: Vec<(&str, &LatencyComparison)> = pallet_endpoints
+                .iter()
+                .chain(&block_endpoints)
+                .chain(&account_endpoints)
+                .chain(&standalone_endpoints)
+                .filter_map(|ep| {
+                    let latency = chain.endpoints.get(*ep)?.latency.as_ref()?;
+                    Some((*ep, latency))
+                })
+                .collect();
+            if !latencies.is_empty() {
+                report.push_str("#### Rust vs Sidecar Latency (latest run)\n\n");
+                report.push_str(&LatencyComparison::markdown_header("Endpoint"));
+                for (endpoint, latency) in latencies {
+                    report.push_str(&latency.markdown_row(endpoint));
+                }
+                report.push('\n');
+            }
         }
 
         report.push_str("## Coverage File Format\n\n");
@@ -791,26 +971,65 @@ impl CoverageData {
                             if let Some(ref pallets) = ep_cov.pallets {
                                 if !pallets.is_empty() {
                                     report.push_str(&format!("### {}\n\n", endpoint));
-                                    report.push_str("| Pallet | Block Ranges | Matched | Mismatched | Rust Err | Sidecar Err | Both Err (diff codes) | Pass Rate |\n");
-                                    report.push_str("|--------|--------------|---------|------------|----------|-------------|----------------------|------------|\n");
+                                    if ep_cov.baseline_stale > 0 {
+                                        report.push_str(&format!(
+                                            "- **Stale baseline entries** (last run): {}\n\n",
+                                            ep_cov.baseline_stale
+                                        ));
+                                    }
+                                    if ep_cov.retries > 0 {
+                                        report.push_str(&format!(
+                                            "- **Retried requests** (transient failures): {}\n\n",
+                                            ep_cov.retries
+                                        ));
+                                    }
+                                    if !ep_cov.errors_by_kind.is_empty() {
+                                        report.push_str(&format!(
+                                            "- **Errors by kind**: {}\n\n",
+                                            ep_cov.errors_by_kind_summary()
+                                        ));
+                                    }
+                                    report.push_str("| Pallet | Block Ranges | Matched | Mismatched (new) | Known | Rust Err | Sidecar Err | Both Err (diff codes) | Error Body Diff | Fork/Desync | Pass Rate |\n");
+                                    report.push_str("|--------|--------------|---------|------------------|-------|----------|-------------|----------------------|-----------------|-------------|------------|\n");
 
                                     let mut sorted_pallets: Vec<_> = pallets.iter().collect();
                                     sorted_pallets.sort_by(|a, b| a.0.cmp(b.0));
 
                                     for (pallet_name, pallet_cov) in sorted_pallets {
                                         report.push_str(&format!(
-                                            "| {} | {} | {} | {} | {} | {} | {} | {:.1}% |\n",
+                                            "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} | {:.1}% |\n",
                                             pallet_name,
                                             format_ranges(&pallet_cov.block_ranges),
                                             pallet_cov.matched,
                                             pallet_cov.mismatched,
+                                            pallet_cov.known,
                                             pallet_cov.rust_errors,
                                             pallet_cov.sidecar_errors,
                                             pallet_cov.both_errors,
+                                            pallet_cov.error_body_mismatches,
+                                            pallet_cov.desyncs,
                                             pallet_cov.pass_rate()
                                         ));
                                     }
-                                    report.push_str("\n");
+                                    report.push('\n');
+
+                                    let mut latencies: Vec<_> = pallets
+                                        .iter()
+                                        .filter_map(|(name, p)| Some((name, p.latency.as_ref()?)))
+                                        .collect();
+                                    latencies.sort_by(|a, b| a.0.cmp(b.0));
+                                    if !latencies.is_empty() {
+                                        report.push_str(
+                                            "**Rust vs Sidecar latency** (latest run):\n\n",
+                                        );
+                                        report.push_str(&LatencyComparison::markdown_header(
+                                            "Pallet",
+                                        ));
+                                        for (name, latency) in latencies {
+                                            report.push_str(&latency.markdown_row(name));
+                                        }
+                                        report.push('\n');
+                                    }
                                 }
                             }
                         }
@@ -836,7 +1055,7 @@ impl CoverageData {
                         chain
                             .endpoints
                             .get(*ep)
-                            .map(|e| e.tested && e.has_issues())
+                            .map(|e| e.tested && (e.has_issues() || e.has_baseline_activity()))
                             .unwrap_or(false)
                     });
 
@@ -851,27 +1070,58 @@ impl CoverageData {
     /// Write a details section for a block-like endpoint (block or account) if it has issues.
     fn write_details_block_section(report: &mut String, chain: &ChainCoverage, endpoint: &str) {
         if let Some(ep_cov) = chain.endpoints.get(endpoint) {
-            if ep_cov.tested && ep_cov.has_issues() {
+            if ep_cov.tested && (ep_cov.has_issues() || ep_cov.has_baseline_activity()) {
                 report.push_str(&format!("### {}\n\n", endpoint));
                 report.push_str(&format!(
                     "- **Block ranges**: {}\n",
                     format_ranges(&ep_cov.block_ranges)
                 ));
-                report.push_str(&format!("- **Pass rate**: {:.1}%\n\n", ep_cov.pass_rate()));
+                report.push_str(&format!("- **Pass rate**: {:.1}%\n", ep_cov.pass_rate()));
+                if ep_cov.baseline_stale > 0 {
+                    report.push_str(&format!(
+                        "- **Stale baseline entries** (last run): {}\n",
+                        ep_cov.baseline_stale
+                    ));
+                }
+                if ep_cov.retries > 0 {
+                    report.push_str(&format!(
+                        "- **Retried requests** (transient failures): {}\n",
+                        ep_cov.retries
+                    ));
+                }
+                if !ep_cov.errors_by_kind.is_empty() {
+                    report.push_str(&format!(
+                        "- **Errors by kind**: {}\n",
+                        ep_cov.errors_by_kind_summary()
+                    ));
+                }
+                report.push('\n');
                 report.push_str(
-                    "| Matched | Mismatched | Rust Err | Sidecar Err | Both Err (diff codes) |\n",
+                    "| Matched | Mismatched (new) | Known | Rust Err | Sidecar Err | Both Err (diff codes) | Error Body Diff | Fork/Desync |\n",
                 );
                 report.push_str(
-                    "|---------|------------|----------|-------------|----------------------|\n",
+                    "|---------|------------------|-------|----------|-------------|----------------------|-----------------|-------------|\n",
                 );
                 report.push_str(&format!(
-                    "| {} | {} | {} | {} | {} |\n\n",
+                    "| {} | {} | {} | {} | {} | {} | {} | {} |\n\n",
                     ep_cov.matched,
                     ep_cov.mismatched,
+                    ep_cov.known,
                     ep_cov.rust_errors,
                     ep_cov.sidecar_errors,
                     ep_cov.both_errors,
+                    ep_cov.error_body_mismatches,
+                    ep_cov.desyncs,
                 ));
+                if let Some(ref latency) = ep_cov.latency {
+                    report.push_str("**Rust vs Sidecar latency** (latest run):\n\n");
+                    report.push_str(&LatencyComparison::markdown_header("Scope"));
+                    report.push_str(&latency.markdown_row("all requests"));
+                    for (account, latency) in &ep_cov.account_latency {
+                        report.push_str(&latency.markdown_row(account));
+                    }
+                    report.push('\n');
+                }
             }
         }
     }
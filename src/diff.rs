@@ -1,19 +1,214 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+
+use crate::rules::{ArrayAlignment, DiffRules, Normalization, NullPolicy};
+
+/// Largest integer an f64 holds exactly (2^53)
+const MAX_EXACT_F64: f64 = 9_007_199_254_740_992.0;
+
+/// Largest LCS table (rust elements x sidecar elements) before falling back
+/// to positional comparison
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Number of serialized bytes kept as the preview of an oversized value
+const PREVIEW_BYTES: usize = 200;
+
+/// Caps on what a comparison keeps in memory
+#[derive(Debug, Clone, Copy)]
+pub struct DiffLimits {
+    /// Maximum number of diffs stored per comparison (0 = unlimited); further diffs are only counted
+    pub max_diffs: usize,
+    /// Values larger than this many serialized bytes are stored as previews (0 = unlimited)
+    pub max_value_bytes: usize,
+}
+
+impl Default for DiffLimits {
+    fn default() -> Self {
+        DiffLimits {
+            max_diffs: 1000,
+            max_value_bytes: 4096,
+        }
+    }
+}
+
+/// A value recorded in a diff: the value itself, or a short preview when it is
+/// larger than `DiffLimits::max_value_bytes` (e.g. a whole extrinsics array)
+#[derive(Debug, Clone)]
+pub enum DiffValue {
+    Full(Value),
+    Preview {
+        type_name: &'static str,
+        /// Element count for arrays and objects
+        len: Option<usize>,
+        /// Start of the serialized value
+        preview: String,
+    },
+}
+
+impl DiffValue {
+    /// Clone `value`, or only capture a preview if it serializes to more than `max_bytes`
+    fn capture(value: &Value, max_bytes: usize) -> Self {
+        let small = match value {
+            Value::Null | Value::Bool(_) | Value::Number(_) => true,
+            _ => max_bytes == 0,
+        };
+        if small {
+            return DiffValue::Full(value.clone());
+        }
+        let mut writer = BoundedWriter {
+            preview: Vec::new(),
+            written: 0,
+            limit: max_bytes,
+        };
+        if serde_json::to_writer(&mut writer, value).is_ok() {
+            return DiffValue::Full(value.clone());
+        }
+        DiffValue::Preview {
+            type_name: value_type_name(Some(value)),
+            len: match value {
+                Value::Array(a) => Some(a.len()),
+                Value::Object(o) => Some(o.len()),
+                _ => None,
+            },
+            preview: String::from_utf8_lossy(&writer.preview)
+                .trim_end_matches('\u{FFFD}')
+                .to_string(),
+        }
+    }
+
+    /// The full value, unless only a preview was kept
+    pub fn as_value(&self) -> Option<&Value> {
+        match self {
+            DiffValue::Full(value) => Some(value),
+            DiffValue::Preview { .. } => None,
+        }
+    }
+
+    /// Short type name of the value (e.g. "array")
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DiffValue::Full(value) => value_type_name(Some(value)),
+            DiffValue::Preview { type_name, .. } => type_name,
+        }
+    }
+
+    /// Number of elements, if the value is an array
+    pub fn array_len(&self) -> Option<usize> {
+        match self {
+            DiffValue::Full(Value::Array(a)) => Some(a.len()),
+            DiffValue::Preview {
+                type_name: "array",
+                len,
+                ..
+            } => *len,
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for DiffValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffValue::Full(value) => write!(f, "{}", truncate_value(value, 100)),
+            DiffValue::Preview {
+                type_name,
+                len,
+                preview,
+            } => {
+                let preview: String = preview.chars().take(100).collect();
+                match len {
+                    Some(len) => write!(
+                        f,
+                        "{}... [{} of {} entries, truncated]",
+                        preview, type_name, len
+                    ),
+                    None => write!(f, "{}... [{}, truncated]", preview, type_name),
+                }
+            }
+        }
+    }
+}
+
+/// Write sink that keeps the first `PREVIEW_BYTES` bytes and fails once more than
+/// `limit` bytes were written, so serializing an oversized value stops early
+struct BoundedWriter {
+    preview: Vec<u8>,
+    written: usize,
+    limit: usize,
+}
+
+impl io::Write for BoundedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let keep = PREVIEW_BYTES
+            .saturating_sub(self.preview.len())
+            .min(buf.len());
+        self.preview.extend_from_slice(&buf[..keep]);
+        self.written += buf.len();
+        if self.written > self.limit {
+            return Err(io::Error::other("value size limit exceeded"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
 
 /// Represents a single difference between two JSON values
 #[derive(Debug, Clone)]
 pub struct JsonDiff {
     /// Path to the differing field (e.g., "at.height" or "extrinsics[0].method")
     pub path: String,
+    /// RFC 6901 JSON Pointer to the differing field (e.g., "/extrinsics/0/method")
+    pub pointer: String,
     /// Value from Rust API (None if field missing)
-    pub rust_value: Option<Value>,
+    pub rust_value: Option<DiffValue>,
     /// Value from Sidecar API (None if field missing)
-    pub sidecar_value: Option<Value>,
+    pub sidecar_value: Option<DiffValue>,
     /// Type of difference
     pub diff_type: DiffType,
+    /// Normalization that made the values equal (for `DiffType::Normalized`)
+    pub normalization: Option<Normalization>,
+    /// Numeric difference, for values compared under a tolerance rule
+    pub delta: Option<NumericDelta>,
 }
 
-#[derive(Debug, Clone)]
+/// Difference between two numeric values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericDelta {
+    /// Absolute difference
+    pub delta: f64,
+    /// Difference as a percentage of the Sidecar value (of the Rust value if Sidecar is 0)
+    pub percent: f64,
+}
+
+impl NumericDelta {
+    fn new(rust: f64, sidecar: f64) -> Self {
+        let delta = (rust - sidecar).abs();
+        let base = if sidecar != 0.0 {
+            sidecar.abs()
+        } else {
+            rust.abs()
+        };
+        let percent = if base == 0.0 {
+            0.0
+        } else {
+            delta / base * 100.0
+        };
+        NumericDelta { delta, percent }
+    }
+}
+
+impl std::fmt::Display for NumericDelta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "delta={} ({:.4}%)", self.delta, self.percent)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DiffType {
     /// Values are different
     ValueMismatch,
@@ -25,10 +220,92 @@ pub enum DiffType {
     ArrayLengthMismatch,
     /// Type mismatch (e.g., string vs number)
     TypeMismatch,
+    /// Aligned array element exists in Sidecar but not in Rust
+    ElementMissingInRust,
+    /// Aligned array element exists in Rust but not in Sidecar
+    ElementMissingInSidecar,
+    /// Unordered arrays hold the same elements in a different order
+    OrderMismatch,
+    /// Same numeric value in a different encoding (e.g. "0x10" vs 16)
+    EncodingOnly,
+    /// Values differ as-is but are equal after a normalization (e.g. ignore-case)
+    Normalized,
+    /// Field is `null` on one side and absent on the other (`--null-policy report`)
+    NullVsMissing,
+    /// Numeric values differ by no more than the path's tolerance
+    WithinTolerance,
+}
+
+impl DiffType {
+    /// Whether this diff describes equivalent values. A response whose diffs
+    /// are all equivalent still counts as a match.
+    pub fn is_equivalent(&self) -> bool {
+        matches!(
+            self,
+            DiffType::EncodingOnly | DiffType::Normalized | DiffType::WithinTolerance
+        )
+    }
+}
+
+impl JsonDiff {
+    /// Path with array indices replaced by `[*]` (e.g. "extrinsics[*].method"),
+    /// so the same field matches across blocks and elements
+    pub fn normalized_path(&self) -> String {
+        let mut normalized = String::with_capacity(self.path.len());
+        let mut in_index = false;
+        for c in self.path.chars() {
+            match c {
+                '[' => {
+                    in_index = true;
+                    normalized.push_str("[*]");
+                }
+                ']' if in_index => in_index = false,
+                _ if in_index => {}
+                _ => normalized.push(c),
+            }
+        }
+        normalized
+    }
+
+    /// Short type names of the Rust and Sidecar values ("missing" when absent)
+    pub fn value_types(&self) -> (&'static str, &'static str) {
+        (
+            self.rust_value
+                .as_ref()
+                .map_or("missing", DiffValue::type_name),
+            self.sidecar_value
+                .as_ref()
+                .map_or("missing", DiffValue::type_name),
+        )
+    }
+
+    /// Fingerprint used to cluster the same diff across blocks:
+    /// normalized path, diff type, normalization and value types
+    pub fn fingerprint(&self) -> String {
+        let (rust_type, sidecar_type) = self.value_types();
+        format!(
+            "{}|{:?}|{}|{}|{}",
+            self.normalized_path(),
+            self.diff_type,
+            self.normalization.map_or(String::new(), |n| n.to_string()),
+            rust_type,
+            sidecar_type
+        )
+    }
 }
 
 impl std::fmt::Display for JsonDiff {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_diff(f)?;
+        match self.delta {
+            Some(delta) => write!(f, ", {}", delta),
+            None => Ok(()),
+        }
+    }
+}
+
+impl JsonDiff {
+    fn fmt_diff(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.diff_type {
             DiffType::ValueMismatch => {
                 write!(
@@ -37,10 +314,10 @@ impl std::fmt::Display for JsonDiff {
                     self.path,
                     self.rust_value
                         .as_ref()
-                        .map_or("null".to_string(), |v| truncate_value(v, 100)),
+                        .map_or("null".to_string(), |v| v.to_string()),
                     self.sidecar_value
                         .as_ref()
-                        .map_or("null".to_string(), |v| truncate_value(v, 100))
+                        .map_or("null".to_string(), |v| v.to_string())
                 )
             }
             DiffType::MissingInSidecar => {
@@ -50,7 +327,7 @@ impl std::fmt::Display for JsonDiff {
                     self.path,
                     self.rust_value
                         .as_ref()
-                        .map_or("null".to_string(), |v| truncate_value(v, 100))
+                        .map_or("null".to_string(), |v| v.to_string())
                 )
             }
             DiffType::MissingInRust => {
@@ -60,7 +337,7 @@ impl std::fmt::Display for JsonDiff {
                     self.path,
                     self.sidecar_value
                         .as_ref()
-                        .map_or("null".to_string(), |v| truncate_value(v, 100))
+                        .map_or("null".to_string(), |v| v.to_string())
                 )
             }
             DiffType::ArrayLengthMismatch => {
@@ -70,12 +347,12 @@ impl std::fmt::Display for JsonDiff {
                     self.path,
                     self.rust_value
                         .as_ref()
-                        .and_then(|v| v.as_array())
-                        .map_or(0, |a| a.len()),
+                        .and_then(DiffValue::array_len)
+                        .unwrap_or(0),
                     self.sidecar_value
                         .as_ref()
-                        .and_then(|v| v.as_array())
-                        .map_or(0, |a| a.len())
+                        .and_then(DiffValue::array_len)
+                        .unwrap_or(0)
                 )
             }
             DiffType::TypeMismatch => {
@@ -83,8 +360,89 @@ impl std::fmt::Display for JsonDiff {
                     f,
                     "{}: type mismatch (rust={} vs sidecar={})",
                     self.path,
-                    value_type_name(self.rust_value.as_ref()),
-                    value_type_name(self.sidecar_value.as_ref())
+                    self.value_types().0,
+                    self.value_types().1
+                )
+            }
+            DiffType::ElementMissingInRust => {
+                write!(
+                    f,
+                    "{}: element missing in rust (sidecar={})",
+                    self.path,
+                    self.sidecar_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string())
+                )
+            }
+            DiffType::ElementMissingInSidecar => {
+                write!(
+                    f,
+                    "{}: element missing in sidecar (rust={})",
+                    self.path,
+                    self.rust_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string())
+                )
+            }
+            DiffType::EncodingOnly => {
+                write!(
+                    f,
+                    "{}: encoding only (rust={} vs sidecar={})",
+                    self.path,
+                    self.rust_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string()),
+                    self.sidecar_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string())
+                )
+            }
+            DiffType::Normalized => {
+                write!(
+                    f,
+                    "{}: equal after {} (rust={} vs sidecar={})",
+                    self.path,
+                    self.normalization
+                        .map_or("normalization".to_string(), |n| n.to_string()),
+                    self.rust_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string()),
+                    self.sidecar_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string())
+                )
+            }
+            DiffType::NullVsMissing => {
+                write!(
+                    f,
+                    "{}: null vs missing (rust={} vs sidecar={})",
+                    self.path,
+                    self.value_types().0,
+                    self.value_types().1
+                )
+            }
+            DiffType::WithinTolerance => {
+                write!(
+                    f,
+                    "{}: within tolerance (rust={} vs sidecar={})",
+                    self.path,
+                    self.rust_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string()),
+                    self.sidecar_value
+                        .as_ref()
+                        .map_or("null".to_string(), |v| v.to_string())
+                )
+            }
+            DiffType::OrderMismatch => {
+                write!(
+                    f,
+                    "{}: same elements, different order ({} elements)",
+                    self.path,
+                    self.rust_value
+                        .as_ref()
+                        .and_then(DiffValue::array_len)
+                        .unwrap_or(0)
                 )
             }
         }
@@ -94,7 +452,7 @@ impl std::fmt::Display for JsonDiff {
 /// Get a short type name for a JSON value
 fn value_type_name(v: Option<&Value>) -> &'static str {
     match v {
-        None => "null",
+        None => "missing",
         Some(Value::Null) => "null",
         Some(Value::Bool(_)) => "bool",
         Some(Value::Number(_)) => "number",
@@ -117,150 +475,1124 @@ fn truncate_value(v: &Value, max_len: usize) -> String {
     }
 }
 
-/// Compare two JSON values for equality, ignoring field order and string case
-pub fn json_equal(a: &Value, b: &Value) -> bool {
-    match (a, b) {
-        (Value::Object(a_map), Value::Object(b_map)) => {
-            if a_map.len() != b_map.len() {
-                return false;
+/// One step in a path through a JSON document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Object key
+    Key(String),
+    /// Array index
+    Index(usize),
+}
+
+/// Render path segments in the dotted display form (e.g. "extrinsics[0].method")
+fn format_path(path: &[PathSegment]) -> String {
+    let mut s = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                if !s.is_empty() {
+                    s.push('.');
+                }
+                s.push_str(key);
             }
-            a_map.iter().all(|(key, a_val)| {
-                b_map
-                    .get(key)
-                    .map_or(false, |b_val| json_equal(a_val, b_val))
-            })
+            PathSegment::Index(i) => s.push_str(&format!("[{}]", i)),
         }
-        (Value::Array(a_arr), Value::Array(b_arr)) => {
-            if a_arr.len() != b_arr.len() {
-                return false;
-            }
-            a_arr
-                .iter()
-                .zip(b_arr.iter())
-                .all(|(a_val, b_val)| json_equal(a_val, b_val))
+    }
+    s
+}
+
+/// Render path segments as an RFC 6901 JSON Pointer (e.g. "/extrinsics/0/method"),
+/// escaping `~` as `~0` and `/` as `~1`
+pub fn format_pointer(path: &[PathSegment]) -> String {
+    let mut s = String::new();
+    for segment in path {
+        s.push('/');
+        match segment {
+            PathSegment::Key(key) => s.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            PathSegment::Index(i) => s.push_str(&i.to_string()),
         }
-        // Case-insensitive string comparison
-        (Value::String(a_str), Value::String(b_str)) => {
-            a_str.to_lowercase() == b_str.to_lowercase()
+    }
+    s
+}
+
+/// State carried through a recursive comparison
+struct DiffContext<'a> {
+    rules: &'a DiffRules,
+    diffs: Vec<JsonDiff>,
+    /// Number of diffs that are not equivalent (see `DiffType::is_equivalent`)
+    hard_diffs: usize,
+    /// Number of diffs counted but not stored (beyond `DiffLimits::max_diffs`)
+    omitted: usize,
+    /// Stop after the first non-equivalent difference (used by `json_equal`)
+    stop_at_first: bool,
+}
+
+impl DiffContext<'_> {
+    fn new(rules: &DiffRules, stop_at_first: bool) -> DiffContext<'_> {
+        DiffContext {
+            rules,
+            diffs: Vec::new(),
+            hard_diffs: 0,
+            omitted: 0,
+            stop_at_first,
         }
-        _ => a == b,
     }
+
+    fn done(&self) -> bool {
+        self.stop_at_first && self.hard_diffs > 0
+    }
+
+    fn push(
+        &mut self,
+        path: &[PathSegment],
+        rust_value: Option<&Value>,
+        sidecar_value: Option<&Value>,
+        diff_type: DiffType,
+    ) -> Option<&mut JsonDiff> {
+        if !diff_type.is_equivalent() {
+            self.hard_diffs += 1;
+        }
+        let limits = self.rules.limits();
+        if limits.max_diffs > 0 && self.diffs.len() >= limits.max_diffs {
+            self.omitted += 1;
+            return None;
+        }
+        let capture = |v: &Value| DiffValue::capture(v, limits.max_value_bytes);
+        self.diffs.push(JsonDiff {
+            path: format_path(path),
+            pointer: format_pointer(path),
+            rust_value: rust_value.map(capture),
+            sidecar_value: sidecar_value.map(capture),
+            diff_type,
+            normalization: None,
+            delta: None,
+        });
+        self.diffs.last_mut()
+    }
+
+    /// Record two numbers compared under a tolerance rule, with their difference
+    fn push_delta(
+        &mut self,
+        path: &[PathSegment],
+        rust_value: &Value,
+        sidecar_value: &Value,
+        diff_type: DiffType,
+        delta: NumericDelta,
+    ) {
+        if let Some(diff) = self.push(path, Some(rust_value), Some(sidecar_value), diff_type) {
+            diff.delta = Some(delta);
+        }
+    }
+
+    /// Record values that are only equal because of `normalization`
+    fn push_normalized(
+        &mut self,
+        path: &[PathSegment],
+        rust_value: &Value,
+        sidecar_value: &Value,
+        normalization: Normalization,
+    ) {
+        if let Some(diff) = self.push(
+            path,
+            Some(rust_value),
+            Some(sidecar_value),
+            DiffType::Normalized,
+        ) {
+            diff.normalization = Some(normalization);
+        }
+    }
+}
+
+/// Compare two JSON values for equality, ignoring field order and
+/// applying the normalization rules for the current scan.
+/// Equivalent diffs (e.g. encoding-only) also make the values unequal here.
+pub fn json_equal(a: &Value, b: &Value, rules: &DiffRules) -> bool {
+    let mut ctx = DiffContext::new(rules, true);
+    json_diff_recursive(a, b, &mut Vec::new(), &mut ctx);
+    ctx.diffs.is_empty() && ctx.omitted == 0
+}
+
+/// Diffs found by `json_diff`
+#[derive(Debug, Default)]
+pub struct DiffOutcome {
+    /// Stored diffs (at most `DiffLimits::max_diffs`)
+    pub diffs: Vec<JsonDiff>,
+    /// Diffs found beyond the limit, counted but not stored
+    pub omitted: usize,
+    /// Number of non-equivalent diffs, stored or not
+    pub hard_diffs: usize,
 }
 
 /// Find all differences between two JSON values.
 /// Results are sorted so non-TypeMismatch diffs appear first,
 /// making it easier to spot real value/structural differences
 /// before the expected type mismatches (e.g., number vs string).
-pub fn json_diff(rust: &Value, sidecar: &Value) -> Vec<JsonDiff> {
-    let mut diffs = Vec::new();
-    json_diff_recursive(rust, sidecar, String::new(), &mut diffs);
+/// Equivalent diffs (e.g. encoding-only) come last.
+/// Large values are stored as previews and the number of stored diffs is
+/// capped, per the scan's `DiffLimits`.
+pub fn json_diff(rust: &Value, sidecar: &Value, rules: &DiffRules) -> DiffOutcome {
+    json_diff_at(rust, sidecar, &mut Vec::new(), rules)
+}
+
+/// Like `json_diff`, for two values found at `path` within their responses,
+/// so the rules for that path apply
+pub fn json_diff_at(
+    rust: &Value,
+    sidecar: &Value,
+    path: &mut Vec<PathSegment>,
+    rules: &DiffRules,
+) -> DiffOutcome {
+    let mut ctx = DiffContext::new(rules, false);
+    json_diff_recursive(rust, sidecar, path, &mut ctx);
+    let mut diffs = ctx.diffs;
     diffs.sort_by_key(|d| match d.diff_type {
+        _ if d.diff_type.is_equivalent() => 2,
         DiffType::TypeMismatch => 1,
         _ => 0,
     });
-    diffs
+    DiffOutcome {
+        diffs,
+        omitted: ctx.omitted,
+        hard_diffs: ctx.hard_diffs,
+    }
+}
+
+/// Apply the string normalizations one at a time (trim, hex lowercasing, case folding)
+/// and return the one after which the two strings became equal, if any
+fn normalized_equal(
+    rust: &str,
+    sidecar: &str,
+    normalizations: &[Normalization],
+) -> Option<Normalization> {
+    let mut rust = rust.to_string();
+    let mut sidecar = sidecar.to_string();
+    for normalization in [
+        Normalization::TrimWhitespace,
+        Normalization::LowercaseHex,
+        Normalization::IgnoreCase,
+    ] {
+        if !normalizations.contains(&normalization) {
+            continue;
+        }
+        let apply = |s: &str| match normalization {
+            Normalization::TrimWhitespace => s.trim().to_string(),
+            Normalization::LowercaseHex if is_hex_string(s) => s.to_lowercase(),
+            Normalization::IgnoreCase => s.to_lowercase(),
+            _ => s.to_string(),
+        };
+        rust = apply(&rust);
+        sidecar = apply(&sidecar);
+        if rust == sidecar {
+            return Some(normalization);
+        }
+    }
+    None
+}
+
+/// Whether a string is a `0x`-prefixed hex value
+fn is_hex_string(s: &str) -> bool {
+    s.strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Whether a numeric string holds the same value as a JSON number ("123" == 123)
+fn number_matches_string(n: &serde_json::Number, s: &str) -> bool {
+    let s = s.trim();
+    if let Ok(parsed) = s.parse::<i128>() {
+        // An integer string only equals a number that is an exact integer
+        return exact_integer(n).is_some_and(|(negative, magnitude)| {
+            parsed.unsigned_abs() == magnitude && (parsed < 0) == negative
+        });
+    }
+    match (s.parse::<f64>(), n.as_f64()) {
+        (Ok(parsed), Some(f)) => parsed == f,
+        _ => false,
+    }
+}
+
+/// Integer value of a JSON number, when it is exact. serde_json keeps the number's
+/// text (`arbitrary_precision`), so integers beyond u64 are parsed from it up to u128.
+/// Other numbers (fractions, exponents) only count when integral and within 2^53,
+/// the range an f64 holds exactly.
+fn exact_integer(n: &serde_json::Number) -> Option<(bool, u128)> {
+    if let Some(u) = n.as_u64() {
+        return Some((false, u as u128));
+    }
+    if let Some(i) = n.as_i64() {
+        return Some((i < 0, i.unsigned_abs() as u128));
+    }
+    let text = n.to_string();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.as_str()),
+    };
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        return digits.parse::<u128>().ok().map(|u| (negative && u != 0, u));
+    }
+    let f = n.as_f64()?;
+    (f.fract() == 0.0 && f.abs() <= MAX_EXACT_F64).then(|| (f < 0.0, f.abs() as u128))
+}
+
+/// Parse a number, decimal string or `0x` hex string as (negative, magnitude).
+/// Strings are exact up to u128; numbers only as far as `exact_integer` allows.
+fn parse_numeric(v: &Value) -> Option<(bool, u128)> {
+    match v {
+        Value::Number(n) => exact_integer(n),
+        Value::String(s) => {
+            let s = s.trim();
+            if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                u128::from_str_radix(hex, 16).ok().map(|u| (false, u))
+            } else if let Some(digits) = s.strip_prefix('-') {
+                digits
+                    .bytes()
+                    .all(|b| b.is_ascii_digit())
+                    .then(|| digits.parse::<u128>().ok())
+                    .flatten()
+                    .map(|u| (u != 0, u))
+            } else {
+                s.bytes()
+                    .all(|b| b.is_ascii_digit())
+                    .then(|| s.parse::<u128>().ok())
+                    .flatten()
+                    .map(|u| (false, u))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Compare two values arithmetically when both parse as numbers.
+/// Returns None when either side is not numeric.
+fn numeric_equal(rust: &Value, sidecar: &Value) -> Option<bool> {
+    let rust_num = parse_numeric(rust)?;
+    let sidecar_num = parse_numeric(sidecar)?;
+    Some(rust_num == sidecar_num)
+}
+
+/// Numeric value of a number, decimal string (integer or fractional) or `0x` hex string
+fn parse_f64(v: &Value) -> Option<f64> {
+    if let Some((negative, magnitude)) = parse_numeric(v) {
+        let f = magnitude as f64;
+        return Some(if negative { -f } else { f });
+    }
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok().filter(|f| f.is_finite()),
+        _ => None,
+    }
+}
+
+/// Report an object field present on only one side, applying the null-vs-missing policy
+fn push_missing_field(
+    path: &[PathSegment],
+    rust_value: Option<&Value>,
+    sidecar_value: Option<&Value>,
+    ctx: &mut DiffContext,
+) {
+    let present = rust_value.or(sidecar_value);
+    let diff_type = match (present, ctx.rules.null_policy()) {
+        (Some(Value::Null), NullPolicy::Equal) => return,
+        (Some(Value::Null), NullPolicy::Report) => DiffType::NullVsMissing,
+        _ if rust_value.is_some() => DiffType::MissingInSidecar,
+        _ => DiffType::MissingInRust,
+    };
+    ctx.push(path, rust_value, sidecar_value, diff_type);
 }
 
 /// Recursively find differences between two JSON values
-fn json_diff_recursive(rust: &Value, sidecar: &Value, path: String, diffs: &mut Vec<JsonDiff>) {
+fn json_diff_recursive(
+    rust: &Value,
+    sidecar: &Value,
+    path: &mut Vec<PathSegment>,
+    ctx: &mut DiffContext,
+) {
+    if ctx.done() {
+        return;
+    }
+
+    let normalizations = ctx.rules.normalizations_at(path);
+    if normalizations.contains(&Normalization::Ignore) {
+        return;
+    }
+
+    // Tolerance: numbers that differ by at most the path's tolerance are equivalent
+    if let Some(tolerance) = ctx.rules.tolerance_at(path) {
+        if let (Some(rust_num), Some(sidecar_num)) = (parse_f64(rust), parse_f64(sidecar)) {
+            let differ = numeric_equal(rust, sidecar).map_or(rust_num != sidecar_num, |eq| !eq);
+            if differ {
+                let diff_type = if tolerance.allows(rust_num, sidecar_num) {
+                    DiffType::WithinTolerance
+                } else {
+                    DiffType::ValueMismatch
+                };
+                let delta = NumericDelta::new(rust_num, sidecar_num);
+                ctx.push_delta(path, rust, sidecar, diff_type, delta);
+                return;
+            }
+        }
+    }
+
+    // Numeric mode: compare by value, and flag equal values in different encodings
+    if normalizations.contains(&Normalization::Numeric) {
+        if let Some(equal) = numeric_equal(rust, sidecar) {
+            if !equal {
+                ctx.push(path, Some(rust), Some(sidecar), DiffType::ValueMismatch);
+            } else if rust != sidecar {
+                ctx.push(path, Some(rust), Some(sidecar), DiffType::EncodingOnly);
+            }
+            return;
+        }
+    }
+
     match (rust, sidecar) {
         (Value::Object(rust_map), Value::Object(sidecar_map)) => {
             // Check for fields in rust but not in sidecar
             for (key, rust_val) in rust_map {
-                let field_path = if path.is_empty() {
-                    key.clone()
-                } else {
-                    format!("{}.{}", path, key)
-                };
-
+                path.push(PathSegment::Key(key.clone()));
                 match sidecar_map.get(key) {
                     Some(sidecar_val) => {
-                        json_diff_recursive(rust_val, sidecar_val, field_path, diffs);
+                        json_diff_recursive(rust_val, sidecar_val, path, ctx);
                     }
                     None => {
-                        diffs.push(JsonDiff {
-                            path: field_path,
-                            rust_value: Some(rust_val.clone()),
-                            sidecar_value: None,
-                            diff_type: DiffType::MissingInSidecar,
-                        });
+                        if !ctx
+                            .rules
+                            .normalizations_at(path)
+                            .contains(&Normalization::Ignore)
+                        {
+                            push_missing_field(path, Some(rust_val), None, ctx);
+                        }
                     }
                 }
+                path.pop();
+                if ctx.done() {
+                    return;
+                }
             }
 
             // Check for fields in sidecar but not in rust
             for (key, sidecar_val) in sidecar_map {
                 if !rust_map.contains_key(key) {
-                    let field_path = if path.is_empty() {
-                        key.clone()
-                    } else {
-                        format!("{}.{}", path, key)
-                    };
-                    diffs.push(JsonDiff {
-                        path: field_path,
-                        rust_value: None,
-                        sidecar_value: Some(sidecar_val.clone()),
-                        diff_type: DiffType::MissingInRust,
-                    });
+                    path.push(PathSegment::Key(key.clone()));
+                    if !ctx
+                        .rules
+                        .normalizations_at(path)
+                        .contains(&Normalization::Ignore)
+                    {
+                        push_missing_field(path, None, Some(sidecar_val), ctx);
+                    }
+                    path.pop();
+                    if ctx.done() {
+                        return;
+                    }
                 }
             }
         }
 
         (Value::Array(rust_arr), Value::Array(sidecar_arr)) => {
-            if rust_arr.len() != sidecar_arr.len() {
-                diffs.push(JsonDiff {
-                    path: path.clone(),
-                    rust_value: Some(Value::Array(rust_arr.clone())),
-                    sidecar_value: Some(Value::Array(sidecar_arr.clone())),
-                    diff_type: DiffType::ArrayLengthMismatch,
-                });
-                // Still compare elements up to the shorter length
-            }
-
-            let min_len = rust_arr.len().min(sidecar_arr.len());
-            for i in 0..min_len {
-                let elem_path = if path.is_empty() {
-                    format!("[{}]", i)
-                } else {
-                    format!("{}[{}]", path, i)
-                };
-                json_diff_recursive(&rust_arr[i], &sidecar_arr[i], elem_path, diffs);
+            match ctx.rules.alignment_at(path) {
+                Some(
+                    ArrayAlignment::Lcs | ArrayAlignment::Keyed(_) | ArrayAlignment::Unordered,
+                ) => diff_aligned_arrays(rust, sidecar, rust_arr, sidecar_arr, path, ctx),
+                Some(ArrayAlignment::Index) | None => {
+                    if rust_arr.len() != sidecar_arr.len() {
+                        ctx.push(
+                            path,
+                            Some(rust),
+                            Some(sidecar),
+                            DiffType::ArrayLengthMismatch,
+                        );
+                        // Still compare elements up to the shorter length
+                    }
+
+                    let min_len = rust_arr.len().min(sidecar_arr.len());
+                    for i in 0..min_len {
+                        path.push(PathSegment::Index(i));
+                        json_diff_recursive(&rust_arr[i], &sidecar_arr[i], path, ctx);
+                        path.pop();
+                        if ctx.done() {
+                            return;
+                        }
+                    }
+                }
             }
         }
 
-        // Case-insensitive string comparison
-        (Value::String(rust_str), Value::String(sidecar_str)) => {
-            if rust_str.to_lowercase() != sidecar_str.to_lowercase() {
-                diffs.push(JsonDiff {
-                    path,
-                    rust_value: Some(rust.clone()),
-                    sidecar_value: Some(sidecar.clone()),
-                    diff_type: DiffType::ValueMismatch,
-                });
+        // String comparison, case-insensitive unless in strict mode
+        (Value::String(rust_str), Value::String(sidecar_str)) if rust_str != sidecar_str => {
+            match normalized_equal(rust_str, sidecar_str, &normalizations) {
+                Some(normalization) => ctx.push_normalized(path, rust, sidecar, normalization),
+                None => {
+                    ctx.push(path, Some(rust), Some(sidecar), DiffType::ValueMismatch);
+                }
             }
         }
 
+        // Numeric string holding the same value as a number, when the rules allow it
+        (Value::Number(n), Value::String(s)) | (Value::String(s), Value::Number(n))
+            if normalizations.contains(&Normalization::NumericString)
+                && number_matches_string(n, s) =>
+        {
+            ctx.push_normalized(path, rust, sidecar, Normalization::NumericString);
+        }
+
         // Type mismatch
         (_, _) if std::mem::discriminant(rust) != std::mem::discriminant(sidecar) => {
-            diffs.push(JsonDiff {
-                path,
-                rust_value: Some(rust.clone()),
-                sidecar_value: Some(sidecar.clone()),
-                diff_type: DiffType::TypeMismatch,
-            });
+            ctx.push(path, Some(rust), Some(sidecar), DiffType::TypeMismatch);
         }
 
         // Same type, different value
         (_, _) if rust != sidecar => {
-            diffs.push(JsonDiff {
-                path,
-                rust_value: Some(rust.clone()),
-                sidecar_value: Some(sidecar.clone()),
-                diff_type: DiffType::ValueMismatch,
-            });
+            ctx.push(path, Some(rust), Some(sidecar), DiffType::ValueMismatch);
         }
 
         // Equal values - no diff
         _ => {}
     }
 }
+
+/// Whether two array elements are equal under the current rules.
+/// The element is addressed by the rust-side index.
+fn elements_equal(
+    rust: &Value,
+    sidecar: &Value,
+    index: usize,
+    path: &mut Vec<PathSegment>,
+    rules: &DiffRules,
+) -> bool {
+    let mut ctx = DiffContext::new(rules, true);
+    path.push(PathSegment::Index(index));
+    json_diff_recursive(rust, sidecar, path, &mut ctx);
+    path.pop();
+    ctx.hard_diffs == 0
+}
+
+/// Report an array element that only one side has, unless the rules ignore it
+fn push_missing_element(
+    element: &Value,
+    index: usize,
+    diff_type: DiffType,
+    path: &mut Vec<PathSegment>,
+    ctx: &mut DiffContext,
+) {
+    path.push(PathSegment::Index(index));
+    if !ctx
+        .rules
+        .normalizations_at(path)
+        .contains(&Normalization::Ignore)
+    {
+        match diff_type {
+            DiffType::ElementMissingInRust => ctx.push(path, None, Some(element), diff_type),
+            _ => ctx.push(path, Some(element), None, diff_type),
+        };
+    }
+    path.pop();
+}
+
+/// Rust and Sidecar index of an aligned array element; `None` on the side that lacks it
+pub type ElementPair = (Option<usize>, Option<usize>);
+
+/// Align the elements of the two arrays at `path` per the array's alignment rule,
+/// in the order their diffs are reported. Arrays without a rule are paired by index.
+pub fn align_arrays(
+    rust_arr: &[Value],
+    sidecar_arr: &[Value],
+    path: &mut Vec<PathSegment>,
+    rules: &DiffRules,
+) -> Vec<ElementPair> {
+    match rules.alignment_at(path) {
+        Some(ArrayAlignment::Lcs) => align_lcs(rust_arr, sidecar_arr, path, rules),
+        Some(ArrayAlignment::Keyed(keys)) => align_keyed(rust_arr, sidecar_arr, keys, path, rules),
+        Some(ArrayAlignment::Unordered) => align_unordered(rust_arr, sidecar_arr, path, rules),
+        Some(ArrayAlignment::Index) | None => (0..rust_arr.len().max(sidecar_arr.len()))
+            .map(|i| {
+                (
+                    (i < rust_arr.len()).then_some(i),
+                    (i < sidecar_arr.len()).then_some(i),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Compare two arrays aligned by `lcs`, `keyed` or `unordered` rules: paired
+/// elements are compared, unpaired ones reported as missing, and for `unordered`
+/// a complete pairing in a different order is reported as one `OrderMismatch`.
+/// Paired and rust-only elements use rust indices; sidecar-only elements use sidecar indices.
+fn diff_aligned_arrays(
+    rust: &Value,
+    sidecar: &Value,
+    rust_arr: &[Value],
+    sidecar_arr: &[Value],
+    path: &mut Vec<PathSegment>,
+    ctx: &mut DiffContext,
+) {
+    let unordered = matches!(
+        ctx.rules.alignment_at(path),
+        Some(ArrayAlignment::Unordered)
+    );
+    let mut all_paired = true;
+    let mut in_order = true;
+    for pair in align_arrays(rust_arr, sidecar_arr, path, ctx.rules) {
+        match pair {
+            (Some(i), Some(j)) => {
+                in_order &= i == j;
+                path.push(PathSegment::Index(i));
+                json_diff_recursive(&rust_arr[i], &sidecar_arr[j], path, ctx);
+                path.pop();
+            }
+            (Some(i), None) => {
+                all_paired = false;
+                push_missing_element(
+                    &rust_arr[i],
+                    i,
+                    DiffType::ElementMissingInSidecar,
+                    path,
+                    ctx,
+                );
+            }
+            (None, Some(j)) => {
+                all_paired = false;
+                push_missing_element(
+                    &sidecar_arr[j],
+                    j,
+                    DiffType::ElementMissingInRust,
+                    path,
+                    ctx,
+                );
+            }
+            (None, None) => {}
+        }
+        if ctx.done() {
+            return;
+        }
+    }
+
+    if unordered && all_paired && !in_order {
+        ctx.push(path, Some(rust), Some(sidecar), DiffType::OrderMismatch);
+    }
+}
+
+/// Pair the unaligned elements between two LCS anchors by position; the
+/// leftovers stay unpaired
+fn align_gap(
+    rust_range: std::ops::Range<usize>,
+    sidecar_range: std::ops::Range<usize>,
+    pairs: &mut Vec<ElementPair>,
+) {
+    let paired = rust_range.len().min(sidecar_range.len());
+    pairs.extend(
+        rust_range
+            .clone()
+            .zip(sidecar_range.clone())
+            .map(|(i, j)| (Some(i), Some(j))),
+    );
+    pairs.extend(rust_range.skip(paired).map(|i| (Some(i), None)));
+    pairs.extend(sidecar_range.skip(paired).map(|j| (None, Some(j))));
+}
+
+/// Align two arrays by longest common subsequence of equal elements.
+/// Elements between two anchors are paired by position.
+fn align_lcs(
+    rust_arr: &[Value],
+    sidecar_arr: &[Value],
+    path: &mut Vec<PathSegment>,
+    rules: &DiffRules,
+) -> Vec<ElementPair> {
+    // Skip the common prefix and suffix, which covers most real-world arrays
+    let mut start = 0;
+    while start < rust_arr.len()
+        && start < sidecar_arr.len()
+        && elements_equal(&rust_arr[start], &sidecar_arr[start], start, path, rules)
+    {
+        start += 1;
+    }
+    let mut rust_end = rust_arr.len();
+    let mut sidecar_end = sidecar_arr.len();
+    while rust_end > start
+        && sidecar_end > start
+        && elements_equal(
+            &rust_arr[rust_end - 1],
+            &sidecar_arr[sidecar_end - 1],
+            rust_end - 1,
+            path,
+            rules,
+        )
+    {
+        rust_end -= 1;
+        sidecar_end -= 1;
+    }
+
+    let rust_mid = &rust_arr[start..rust_end];
+    let sidecar_mid = &sidecar_arr[start..sidecar_end];
+    let n = rust_mid.len();
+    let m = sidecar_mid.len();
+
+    // lengths[i * (m + 1) + j] = LCS length of rust_mid[i..] and sidecar_mid[j..]
+    let mut anchors = Vec::new();
+    if n > 0 && m > 0 && n * m <= MAX_LCS_CELLS {
+        let mut equal = vec![false; n * m];
+        for i in 0..n {
+            for j in 0..m {
+                equal[i * m + j] =
+                    elements_equal(&rust_mid[i], &sidecar_mid[j], start + i, path, rules);
+            }
+        }
+        let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i * (m + 1) + j] = if equal[i * m + j] {
+                    lengths[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if equal[i * m + j] {
+                anchors.push((i, j));
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * (m + 1) + j] >= lengths[i * (m + 1) + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<ElementPair> = (0..start).map(|k| (Some(k), Some(k))).collect();
+    let (mut prev_i, mut prev_j) = (0, 0);
+    for (i, j) in anchors {
+        align_gap(
+            start + prev_i..start + i,
+            start + prev_j..start + j,
+            &mut pairs,
+        );
+        pairs.push((Some(start + i), Some(start + j)));
+        prev_i = i + 1;
+        prev_j = j + 1;
+    }
+    align_gap(
+        start + prev_i..rust_end,
+        start + prev_j..sidecar_end,
+        &mut pairs,
+    );
+    pairs.extend(
+        (rust_end..rust_arr.len())
+            .zip(sidecar_end..sidecar_arr.len())
+            .map(|(i, j)| (Some(i), Some(j))),
+    );
+    pairs
+}
+
+/// Key of the array element at `index`: the values at each dotted key path.
+/// Strings are unquoted, and lowercased where the rules ignore case at the key's path.
+fn element_key(
+    element: &Value,
+    index: usize,
+    keys: &[String],
+    path: &mut Vec<PathSegment>,
+    rules: &DiffRules,
+) -> Vec<String> {
+    keys.iter()
+        .map(|key| {
+            let value = key.split('.').try_fold(element, |v, part| v.get(part));
+            match value {
+                Some(Value::String(s)) => {
+                    let depth = path.len();
+                    path.push(PathSegment::Index(index));
+                    path.extend(
+                        key.split('.')
+                            .map(|part| PathSegment::Key(part.to_string())),
+                    );
+                    let ignore_case = rules
+                        .normalizations_at(path)
+                        .contains(&Normalization::IgnoreCase);
+                    path.truncate(depth);
+                    if ignore_case {
+                        s.to_lowercase()
+                    } else {
+                        s.clone()
+                    }
+                }
+                Some(v) => v.to_string(),
+                None => "null".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Align two arrays by element key. Elements sharing a key are paired in order.
+fn align_keyed(
+    rust_arr: &[Value],
+    sidecar_arr: &[Value],
+    keys: &[String],
+    path: &mut Vec<PathSegment>,
+    rules: &DiffRules,
+) -> Vec<ElementPair> {
+    let mut sidecar_by_key: HashMap<Vec<String>, VecDeque<usize>> = HashMap::new();
+    for (j, element) in sidecar_arr.iter().enumerate() {
+        sidecar_by_key
+            .entry(element_key(element, j, keys, path, rules))
+            .or_default()
+            .push_back(j);
+    }
+
+    let mut sidecar_matched = vec![false; sidecar_arr.len()];
+    let mut pairs = Vec::new();
+    for (i, element) in rust_arr.iter().enumerate() {
+        let matched = sidecar_by_key
+            .get_mut(&element_key(element, i, keys, path, rules))
+            .and_then(|indices| indices.pop_front());
+        if let Some(j) = matched {
+            sidecar_matched[j] = true;
+        }
+        pairs.push((Some(i), matched));
+    }
+    pairs.extend(
+        (0..sidecar_arr.len())
+            .filter(|&j| !sidecar_matched[j])
+            .map(|j| (None, Some(j))),
+    );
+    pairs
+}
+
+/// Align two arrays as multisets: each rust element is paired with an equal,
+/// unused sidecar element
+fn align_unordered(
+    rust_arr: &[Value],
+    sidecar_arr: &[Value],
+    path: &mut Vec<PathSegment>,
+    rules: &DiffRules,
+) -> Vec<ElementPair> {
+    let mut sidecar_matched = vec![false; sidecar_arr.len()];
+    let mut pairs = Vec::new();
+    for (i, element) in rust_arr.iter().enumerate() {
+        // Try the same position first, which is the common case
+        let same_position = i < sidecar_arr.len()
+            && !sidecar_matched[i]
+            && elements_equal(element, &sidecar_arr[i], i, path, rules);
+        let matched = if same_position {
+            Some(i)
+        } else {
+            (0..sidecar_arr.len()).find(|&j| {
+                !sidecar_matched[j] && elements_equal(element, &sidecar_arr[j], i, path, rules)
+            })
+        };
+        if let Some(j) = matched {
+            sidecar_matched[j] = true;
+        }
+        pairs.push((Some(i), matched));
+    }
+    pairs.extend(
+        (0..sidecar_arr.len())
+            .filter(|&j| !sidecar_matched[j])
+            .map(|j| (None, Some(j))),
+    );
+    pairs
+}
+
+/// Build an RFC 6902 JSON Patch that transforms the Rust response into the Sidecar
+/// response, from the diffs of one mismatch. Paths skipped by `ignore` rules
+/// are not part of the patch.
+///
+/// Whole-value operations come first, in Rust index space. Aligned-array element
+/// operations follow, deepest arrays first; per array, removals run from the highest
+/// Rust index down and additions from the lowest Sidecar index up. For `keyed` and
+/// `unordered` alignment the patch reproduces the Sidecar elements, not their order.
+/// Additions and replacements whose Sidecar value was only kept as a preview are left out.
+pub fn json_patch(diffs: &[JsonDiff]) -> Value {
+    // Arrays replaced as a whole make any diff below them redundant
+    let replaced: Vec<&str> = diffs
+        .iter()
+        .filter(|d| {
+            matches!(
+                d.diff_type,
+                DiffType::ArrayLengthMismatch | DiffType::OrderMismatch
+            ) && patch_value(d).is_some()
+        })
+        .map(|d| d.pointer.as_str())
+        .collect();
+    let is_replaced = |pointer: &str| {
+        replaced.iter().any(|r| {
+            pointer.len() > r.len() && pointer.starts_with(r) && pointer.as_bytes()[r.len()] == b'/'
+        })
+    };
+
+    let mut ops = Vec::new();
+    let mut element_ops: Vec<&JsonDiff> = Vec::new();
+    for diff in diffs {
+        if is_replaced(&diff.pointer) {
+            continue;
+        }
+        match diff.diff_type {
+            DiffType::ValueMismatch
+            | DiffType::TypeMismatch
+            | DiffType::ArrayLengthMismatch
+            | DiffType::OrderMismatch
+            | DiffType::EncodingOnly
+            | DiffType::Normalized
+            | DiffType::WithinTolerance => {
+                if let Some(value) = patch_value(diff) {
+                    ops.push(serde_json::json!({
+                        "op": "replace",
+                        "path": diff.pointer,
+                        "value": value,
+                    }))
+                }
+            }
+            DiffType::MissingInSidecar => ops.push(serde_json::json!({
+                "op": "remove",
+                "path": diff.pointer,
+            })),
+            DiffType::NullVsMissing if diff.rust_value.is_some() => ops.push(serde_json::json!({
+                "op": "remove",
+                "path": diff.pointer,
+            })),
+            DiffType::NullVsMissing => ops.push(serde_json::json!({
+                "op": "add",
+                "path": diff.pointer,
+                "value": Value::Null,
+            })),
+            DiffType::MissingInRust => {
+                if let Some(value) = patch_value(diff) {
+                    ops.push(serde_json::json!({
+                        "op": "add",
+                        "path": diff.pointer,
+                        "value": value,
+                    }))
+                }
+            }
+            DiffType::ElementMissingInRust | DiffType::ElementMissingInSidecar => {
+                element_ops.push(diff)
+            }
+        }
+    }
+
+    // Group element operations by array: (array pointer, index, diff)
+    let mut elements: Vec<(&str, usize, &JsonDiff)> = element_ops
+        .into_iter()
+        .filter_map(|d| {
+            let (parent, index) = d.pointer.rsplit_once('/')?;
+            Some((parent, index.parse().ok()?, d))
+        })
+        .collect();
+    elements.sort_by(|a, b| {
+        let depth = |p: &str| p.matches('/').count();
+        depth(b.0)
+            .cmp(&depth(a.0))
+            .then_with(|| a.0.cmp(b.0))
+            .then_with(|| {
+                let rank = |d: &JsonDiff| match d.diff_type {
+                    DiffType::ElementMissingInSidecar => 0,
+                    _ => 1,
+                };
+                rank(a.2).cmp(&rank(b.2))
+            })
+            .then_with(|| match a.2.diff_type {
+                DiffType::ElementMissingInSidecar => b.1.cmp(&a.1),
+                _ => a.1.cmp(&b.1),
+            })
+    });
+    for (_, _, diff) in elements {
+        match diff.diff_type {
+            DiffType::ElementMissingInSidecar => ops.push(serde_json::json!({
+                "op": "remove",
+                "path": diff.pointer,
+            })),
+            _ => {
+                if let Some(value) = patch_value(diff) {
+                    ops.push(serde_json::json!({
+                        "op": "add",
+                        "path": diff.pointer,
+                        "value": value,
+                    }))
+                }
+            }
+        }
+    }
+
+    Value::Array(ops)
+}
+
+/// Sidecar value to write in a patch operation (None if only a preview was kept)
+fn patch_value(diff: &JsonDiff) -> Option<Value> {
+    match &diff.sidecar_value {
+        None => Some(Value::Null),
+        Some(value) => value.as_value().cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::Chain;
+    use crate::endpoints::EndpointType;
+    use crate::rules::RulesFile;
+    use serde_json::json;
+
+    /// Rules for a block scan, loaded from a rules file with the given content
+    fn rules(content: Value, strict: bool) -> DiffRules {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let file =
+            std::env::temp_dir().join(format!("diff-rules-{}-{}.json", std::process::id(), n));
+        std::fs::write(&file, content.to_string()).unwrap();
+        let rules_file = RulesFile::load(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        rules_file.for_scope(
+            &Chain::Polkadot,
+            &EndpointType::Block,
+            strict,
+            NullPolicy::default(),
+            DiffLimits::default(),
+        )
+    }
+
+    fn align(rust: &Value, sidecar: &Value, rules: &DiffRules) -> Vec<ElementPair> {
+        let mut path = vec![PathSegment::Key("a".to_string())];
+        align_arrays(
+            rust.as_array().unwrap(),
+            sidecar.as_array().unwrap(),
+            &mut path,
+            rules,
+        )
+    }
+
+    /// Apply an RFC 6902 patch of `add`, `remove` and `replace` operations
+    fn apply_patch(mut target: Value, patch: &Value) -> Value {
+        for op in patch.as_array().unwrap() {
+            let pointer = op["path"].as_str().unwrap();
+            let (parent, last) = pointer.rsplit_once('/').unwrap();
+            let container = target.pointer_mut(parent).unwrap();
+            match (op["op"].as_str().unwrap(), container) {
+                ("replace", _) => *target.pointer_mut(pointer).unwrap() = op["value"].clone(),
+                ("add", Value::Array(arr)) => {
+                    arr.insert(last.parse().unwrap(), op["value"].clone())
+                }
+                ("add", Value::Object(obj)) => {
+                    obj.insert(last.to_string(), op["value"].clone());
+                }
+                ("remove", Value::Array(arr)) => {
+                    arr.remove(last.parse().unwrap());
+                }
+                ("remove", Value::Object(obj)) => {
+                    obj.remove(last);
+                }
+                (other, _) => panic!("unexpected patch operation {}", other),
+            }
+        }
+        target
+    }
+
+    #[test]
+    fn lcs_reports_insertion_in_the_middle() {
+        let rules = rules(json!({"rules": [{"path": "a", "align": "lcs"}]}), false);
+        let rust = json!({"a": [1, 2, 4, 5]});
+        let sidecar = json!({"a": [1, 2, 3, 4, 5]});
+
+        let outcome = json_diff(&rust, &sidecar, &rules);
+        assert_eq!(outcome.diffs.len(), 1);
+        assert_eq!(outcome.diffs[0].diff_type, DiffType::ElementMissingInRust);
+        assert_eq!(outcome.diffs[0].pointer, "/a/2");
+
+        let pairs = align(&rust["a"], &sidecar["a"], &rules);
+        assert_eq!(
+            pairs,
+            vec![
+                (Some(0), Some(0)),
+                (Some(1), Some(1)),
+                (None, Some(2)),
+                (Some(2), Some(3)),
+                (Some(3), Some(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn keyed_alignment_ignores_reordering() {
+        let rules = rules(
+            json!({"rules": [{"path": "a", "align": {"keyed": ["id"]}}]}),
+            false,
+        );
+        let rust = json!({"a": [{"id": "X", "v": 1}, {"id": "y", "v": 2}]});
+        let sidecar = json!({"a": [{"id": "Y", "v": 2}, {"id": "x", "v": 3}]});
+
+        let outcome = json_diff(&rust, &sidecar, &rules);
+        let hard: Vec<_> = outcome
+            .diffs
+            .iter()
+            .filter(|d| !d.diff_type.is_equivalent())
+            .collect();
+        assert_eq!(hard.len(), 1);
+        assert_eq!(hard[0].diff_type, DiffType::ValueMismatch);
+        assert_eq!(hard[0].pointer, "/a/0/v");
+        assert!(!outcome
+            .diffs
+            .iter()
+            .any(|d| d.diff_type == DiffType::OrderMismatch));
+    }
+
+    #[test]
+    fn keyed_alignment_is_case_sensitive_when_strict() {
+        let rules = rules(
+            json!({"rules": [{"path": "a", "align": {"keyed": ["id"]}}]}),
+            true,
+        );
+        let rust = json!({"a": [{"id": "X"}]});
+        let sidecar = json!({"a": [{"id": "x"}]});
+
+        assert_eq!(
+            align(&rust["a"], &sidecar["a"], &rules),
+            vec![(Some(0), None), (None, Some(0))]
+        );
+    }
+
+    #[test]
+    fn lcs_falls_back_to_positional_pairing_above_the_cell_limit() {
+        let rules = rules(json!({"rules": [{"path": "a", "align": "lcs"}]}), false);
+        // 2001 x 2001 differing elements exceed MAX_LCS_CELLS after trimming
+        let mut rust: Vec<Value> = vec![json!("r")];
+        rust.extend((0..2000).map(|n| json!(n)));
+        let mut sidecar: Vec<Value> = (0..2000).map(|n| json!(n)).collect();
+        sidecar.push(json!("s"));
+        assert!(rust.len() * sidecar.len() > MAX_LCS_CELLS);
+
+        let pairs = align(&Value::Array(rust), &Value::Array(sidecar), &rules);
+        assert_eq!(pairs.len(), 2001);
+        assert!(pairs
+            .iter()
+            .enumerate()
+            .all(|(k, &p)| p == (Some(k), Some(k))));
+    }
+
+    #[test]
+    fn patch_turns_rust_into_sidecar() {
+        let rules = rules(
+            json!({"rules": [
+                {"path": "a", "align": "lcs"},
+                {"path": "e", "align": {"keyed": ["k"]}},
+            ]}),
+            true,
+        );
+        let rust = json!({
+            "a": [1, 2, 4, 5, 6],
+            "e": [{"k": 1, "v": "a"}, {"k": 2, "v": "b"}],
+            "n": {"x": 1, "gone": true},
+            "t": "1",
+        });
+        let sidecar = json!({
+            "a": [0, 1, 2, 3, 4, 6],
+            "e": [{"k": 1, "v": "A"}, {"k": 2, "v": "b"}, {"k": 3, "v": "c"}],
+            "n": {"x": 2, "new": [1]},
+            "t": 1,
+        });
+
+        let outcome = json_diff(&rust, &sidecar, &rules);
+        assert_eq!(outcome.omitted, 0);
+        let patched = apply_patch(rust, &json_patch(&outcome.diffs));
+        assert_eq!(patched, sidecar);
+    }
+}
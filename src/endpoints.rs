@@ -535,8 +535,11 @@ impl EndpointType {
     }
 }
 
+/// Endpoint name entry: (variant, canonical name, aliases)
+type EndpointName = (fn() -> EndpointType, &'static str, &'static [&'static str]);
+
 /// Each entry: (variant, canonical name, aliases)
-const ENDPOINT_NAMES: &[(fn() -> EndpointType, &str, &[&str])] = &[
+const ENDPOINT_NAMES: &[EndpointName] = &[
     // Account
     (
         || EndpointType::AccountBalanceInfo,
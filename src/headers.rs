@@ -0,0 +1,242 @@
+//! Response header comparison.
+//!
+//! Only the bodies are diffed by default, but caches and browsers also depend on
+//! headers such as `content-type`, `cache-control` and the CORS headers. With
+//! `--compare-headers`, a fixed set of these headers plus any custom ones is
+//! compared for every response pair with the same status. Values are compared
+//! ignoring case and whitespace. Mismatches are grouped by header and value pair
+//! and reported in their own section, separately from body mismatches.
+
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::chains::Chain;
+use crate::cluster::{Group, GroupKey, GroupSet};
+use crate::endpoints::EndpointType;
+
+/// Headers compared by default: content negotiation, caching and CORS
+pub const DEFAULT_HEADERS: &[&str] = &[
+    "content-type",
+    "content-encoding",
+    "cache-control",
+    "vary",
+    "access-control-allow-origin",
+    "access-control-allow-methods",
+    "access-control-allow-headers",
+    "access-control-allow-credentials",
+    "access-control-expose-headers",
+    "access-control-max-age",
+];
+
+/// One header differing between the two responses
+#[derive(Debug, Clone)]
+pub struct HeaderDifference {
+    /// Lowercase header name
+    pub header: String,
+    /// Value on the Rust side (None when missing)
+    pub rust: Option<String>,
+    /// Value on the Sidecar side (None when missing)
+    pub sidecar: Option<String>,
+}
+
+impl fmt::Display for HeaderDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<String>| match value {
+            Some(value) => format!("\"{}\"", value),
+            None => "(missing)".to_string(),
+        };
+        write!(
+            f,
+            "{}: rust={} vs sidecar={}",
+            self.header,
+            show(&self.rust),
+            show(&self.sidecar)
+        )
+    }
+}
+
+impl GroupKey for HeaderDifference {
+    fn label(&self) -> &str {
+        &self.header
+    }
+}
+
+/// All response pairs with one header differing in the same way
+pub type HeaderMismatch = Group<HeaderDifference>;
+
+/// Mismatches collected over one scan
+#[derive(Debug, Default)]
+struct MismatchSet {
+    /// Response pairs whose headers were compared
+    compared: u64,
+    /// Response pairs with at least one differing header
+    differing: u64,
+    /// Grouped by header and value pair
+    groups: GroupSet<HeaderDifference>,
+}
+
+/// Header comparison state shared by the requests of one scan
+#[derive(Debug)]
+pub struct HeaderCheck {
+    /// Lowercase names of the compared headers
+    names: Vec<String>,
+    mismatches: Mutex<MismatchSet>,
+}
+
+impl HeaderCheck {
+    /// Compare the default headers plus `custom` ones
+    pub fn new(custom: &[String]) -> Self {
+        let mut names: Vec<String> = DEFAULT_HEADERS.iter().map(|h| h.to_string()).collect();
+        for name in custom {
+            let name = name.trim().to_ascii_lowercase();
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        HeaderCheck {
+            names,
+            mismatches: Mutex::new(MismatchSet::default()),
+        }
+    }
+
+    /// Lowercase names of the compared headers
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Compare the selected headers of one response pair and record the differences
+    pub fn compare(
+        &self,
+        block: u64,
+        url: &str,
+        rust: &[(String, String)],
+        sidecar: &[(String, String)],
+    ) {
+        let differing: Vec<(&String, Option<String>, Option<String>)> = self
+            .names
+            .iter()
+            .map(|name| (name, header_value(rust, name), header_value(sidecar, name)))
+            .filter(|(_, r, s)| r.as_deref().map(normalize) != s.as_deref().map(normalize))
+            .collect();
+
+        let mut set = self.mismatches.lock().unwrap();
+        set.compared += 1;
+        if differing.is_empty() {
+            return;
+        }
+        set.differing += 1;
+        set.groups.add(
+            block,
+            url,
+            differing.into_iter().map(|(name, rust, sidecar)| {
+                (
+                    format!("{}|{:?}|{:?}", name, rust, sidecar),
+                    HeaderDifference {
+                        header: name.clone(),
+                        rust,
+                        sidecar,
+                    },
+                )
+            }),
+        );
+    }
+
+    /// Mismatches ordered by occurrence count (most frequent first)
+    pub fn sorted_mismatches(&self) -> Vec<HeaderMismatch> {
+        let set = self.mismatches.lock().unwrap();
+        set.groups.sorted().into_iter().cloned().collect()
+    }
+
+    /// (compared response pairs, pairs with at least one differing header)
+    pub fn counts(&self) -> (u64, u64) {
+        let set = self.mismatches.lock().unwrap();
+        (set.compared, set.differing)
+    }
+
+    pub fn print_summary(&self) {
+        let (compared, differing) = self.counts();
+        println!("\n{}", "=".repeat(90));
+        println!("                              HEADER MISMATCHES");
+        println!("{}", "=".repeat(90));
+        println!("Headers: {}", self.names.join(", "));
+        println!(
+            "Response pairs compared: {}, with differing headers: {}",
+            compared, differing
+        );
+        let groups = self.sorted_mismatches();
+        if groups.is_empty() {
+            return;
+        }
+        println!("\nMismatches (by response pairs affected):");
+        for group in &groups {
+            println!(
+                "  {:>6}x  {} (blocks {}-{})",
+                group.occurrences, group, group.first_block, group.last_block
+            );
+            for url in &group.example_urls {
+                println!("           {}", url);
+            }
+        }
+    }
+
+    pub fn to_markdown(&self, chain: &Chain, endpoint: &EndpointType) -> String {
+        let (compared, differing) = self.counts();
+        let mut md = String::new();
+        md.push_str(&format!(
+            "# Header Mismatches: {} `{}`\n\n",
+            chain, endpoint
+        ));
+        md.push_str(&format!(
+            "- **Headers**: {}\n- **Response pairs compared**: {}\n- **With differing headers**: {}\n\n",
+            self.names
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            compared,
+            differing
+        ));
+        let groups = self.sorted_mismatches();
+        if groups.is_empty() {
+            md.push_str("No header mismatches.\n");
+            return md;
+        }
+        let cell = |value: &Option<String>| match value {
+            Some(value) => format!("`{}`", value.replace('|', "\\|")),
+            None => "(missing)".to_string(),
+        };
+        md.push_str("| Responses | Header | Rust | Sidecar | Blocks | Example |\n");
+        md.push_str("|-----------|--------|------|---------|--------|---------|\n");
+        for group in &groups {
+            md.push_str(&format!(
+                "| {} | `{}` | {} | {} | {}-{} | {} |\n",
+                group.occurrences,
+                group.key.header,
+                cell(&group.key.rust),
+                cell(&group.key.sidecar),
+                group.first_block,
+                group.last_block,
+                group.example_urls.first().map_or("", String::as_str)
+            ));
+        }
+        md
+    }
+}
+
+/// All values of a header joined with ", " (names are lowercase), or None when absent
+fn header_value(headers: &[(String, String)], name: &str) -> Option<String> {
+    let values: Vec<&str> = headers
+        .iter()
+        .filter(|(n, _)| n == name)
+        .map(|(_, v)| v.trim())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+/// Header value with case and whitespace folded
+fn normalize(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
@@ -0,0 +1,477 @@
+//! Self-contained HTML mismatch report.
+//!
+//! Written next to the markdown reports (`--report`), as a single file with inline
+//! CSS and JS and no external resources, so it can be opened straight from a CI
+//! artifact. Issues are embedded as JSON and rendered client-side as a filterable
+//! table (endpoint, pallet, account, result kind, diff type). Expanding an issue
+//! shows a tree of the differing paths, with the Rust and Sidecar values of each
+//! differing node as expandable JSON trees.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+
+use crate::chains::Chain;
+use crate::diff::{DiffType, DiffValue, JsonDiff};
+use crate::endpoints::EndpointType;
+use crate::http::TestResult;
+
+/// Maximum number of diffs embedded per issue
+const MAX_HTML_DIFFS: usize = 200;
+
+/// One differing node of an issue
+#[derive(Debug, Serialize)]
+struct HtmlDiff {
+    path: String,
+    pointer: String,
+    diff_type: DiffType,
+    /// Full diff line as printed in the console and logs
+    text: String,
+    rust: Option<Value>,
+    sidecar: Option<Value>,
+    /// Whether a value was only kept as a preview (see `--max-value-size`)
+    truncated: bool,
+}
+
+impl HtmlDiff {
+    fn new(diff: &JsonDiff) -> Self {
+        let value = |v: &Option<DiffValue>| {
+            v.as_ref().map(|v| match v.as_value() {
+                Some(value) => value.clone(),
+                None => Value::String(v.to_string()),
+            })
+        };
+        HtmlDiff {
+            path: diff.path.clone(),
+            pointer: diff.pointer.clone(),
+            diff_type: diff.diff_type,
+            text: diff.to_string(),
+            rust: value(&diff.rust_value),
+            sidecar: value(&diff.sidecar_value),
+            truncated: [&diff.rust_value, &diff.sidecar_value]
+                .iter()
+                .any(|v| matches!(v, Some(DiffValue::Preview { .. }))),
+        }
+    }
+}
+
+/// One non-matching result
+#[derive(Debug, Serialize)]
+struct HtmlIssue {
+    block: u64,
+    url: String,
+    pallet: Option<String>,
+    account: Option<String>,
+    /// "mismatch", "known", "rust-error", "sidecar-error", "both-error", "error-body" or "desync"
+    kind: &'static str,
+    message: String,
+    diffs: Vec<HtmlDiff>,
+    /// Diffs found but not embedded
+    omitted: usize,
+}
+
+/// Result counts shown in the report header
+#[derive(Debug, Default, Serialize)]
+struct HtmlCounts {
+    matched: u32,
+    mismatched: u32,
+    known: u32,
+    rust_errors: u32,
+    sidecar_errors: u32,
+    both_errors: u32,
+    error_body_mismatches: u32,
+    desyncs: u32,
+}
+
+/// Issues collected over one run for the HTML report
+#[derive(Debug, Default)]
+pub struct HtmlReport {
+    /// Pallet of the results currently being added
+    pallet: Option<String>,
+    /// Account label of the results currently being added
+    account: Option<String>,
+    counts: HtmlCounts,
+    issues: Vec<HtmlIssue>,
+}
+
+impl HtmlReport {
+    /// Set the pallet and account that the following results belong to
+    pub fn set_scope(&mut self, pallet: Option<&str>, account: Option<&str>) {
+        self.pallet = pallet.map(str::to_string);
+        self.account = account.map(str::to_string);
+    }
+
+    /// Record one result (`known` = mismatch made up only of baselined diffs)
+    pub fn add(&mut self, block: u64, url: &str, result: &TestResult, known: bool) {
+        if result.counts_as_match() {
+            self.counts.matched += 1;
+        }
+        let (kind, message, diffs, omitted) = match result {
+            TestResult::Match { .. } => return,
+            TestResult::BothError {
+                rust_error,
+                sidecar_error,
+            } if rust_error.same_as(sidecar_error) => return,
+            TestResult::Mismatch {
+                diffs,
+                omitted_diffs,
+                ..
+            } => {
+                let total = diffs.len() + omitted_diffs;
+                let kind = if known {
+                    self.counts.known += 1;
+                    "known"
+                } else {
+                    self.counts.mismatched += 1;
+                    "mismatch"
+                };
+                (
+                    kind,
+                    format!("{} difference(s)", total),
+                    diffs
+                        .iter()
+                        .take(MAX_HTML_DIFFS)
+                        .map(HtmlDiff::new)
+                        .collect(),
+                    total - diffs.len().min(MAX_HTML_DIFFS),
+                )
+            }
+            TestResult::RustError(err) => {
+                self.counts.rust_errors += 1;
+                ("rust-error", err.to_string(), Vec::new(), 0)
+            }
+            TestResult::SidecarError(err) => {
+                self.counts.sidecar_errors += 1;
+                ("sidecar-error", err.to_string(), Vec::new(), 0)
+            }
+            TestResult::BothError {
+                rust_error,
+                sidecar_error,
+            } => {
+                self.counts.both_errors += 1;
+                (
+                    "both-error",
+                    format!("Rust: {}, Sidecar: {}", rust_error, sidecar_error),
+                    Vec::new(),
+                    0,
+                )
+            }
+            TestResult::ErrorBodyMismatch {
+                rust_error,
+                diffs,
+                omitted_diffs,
+                ..
+            } => {
+                self.counts.error_body_mismatches += 1;
+                let total = diffs.len() + omitted_diffs;
+                (
+                    "error-body",
+                    format!(
+                        "{} difference(s) between the {} bodies",
+                        total,
+                        rust_error.category()
+                    ),
+                    diffs
+                        .iter()
+                        .take(MAX_HTML_DIFFS)
+                        .map(HtmlDiff::new)
+                        .collect(),
+                    total - diffs.len().min(MAX_HTML_DIFFS),
+                )
+            }
+            TestResult::Desync { rust, sidecar } => {
+                self.counts.desyncs += 1;
+                (
+                    "desync",
+                    format!("Rust API block {}, Sidecar block {}", rust, sidecar),
+                    Vec::new(),
+                    0,
+                )
+            }
+        };
+        self.issues.push(HtmlIssue {
+            block,
+            url: url.to_string(),
+            pallet: self.pallet.clone(),
+            account: self.account.clone(),
+            kind,
+            message,
+            diffs,
+            omitted,
+        });
+    }
+
+    /// Write `report_{chain}_{start}-{end}_{endpoint}.html` (only when there are issues)
+    pub fn write(
+        &self,
+        chain: &Chain,
+        endpoint_type: &EndpointType,
+        start_block: u32,
+        end_block: u32,
+        rust_url: &str,
+        sidecar_url: &str,
+    ) {
+        if self.issues.is_empty() {
+            return;
+        }
+
+        let filename = format!(
+            "report_{}_{}-{}_{}.html",
+            chain, start_block, end_block, endpoint_type
+        );
+        let data = serde_json::json!({
+            "chain": chain.to_string(),
+            "endpoint": endpoint_type.to_string(),
+            "startBlock": start_block,
+            "endBlock": end_block,
+            "rustUrl": rust_url,
+            "sidecarUrl": sidecar_url,
+            "generated": chrono::Utc::now().to_rfc3339(),
+            "counts": self.counts,
+            "issues": self.issues,
+        });
+        // `<` only occurs inside JSON strings; escaping it keeps the data from
+        // closing the <script> element early
+        let data = data.to_string().replace('<', "\\u003c");
+        let title = format!("Mismatch Report: {} {}", chain, endpoint_type);
+        let html = HTML_TEMPLATE
+            .replace("{{TITLE}}", &escape_html(&title))
+            .replace("{{DATA}}", &data);
+
+        match fs::write(&filename, html) {
+            Ok(()) => println!("HTML report saved to: {}", filename),
+            Err(e) => eprintln!("Failed to create HTML report {}: {}", filename, e),
+        }
+    }
+}
+
+/// Escape text for use in HTML element content
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{TITLE}}</title>
+<style>
+  body { font-family: -apple-system, Segoe UI, Helvetica, Arial, sans-serif; margin: 24px; color: #1f2328; }
+  h1 { font-size: 22px; margin-bottom: 4px; }
+  .meta { color: #59636e; font-size: 13px; margin-bottom: 16px; }
+  .counts span { display: inline-block; margin-right: 16px; font-size: 14px; }
+  .filters { margin: 16px 0; display: flex; flex-wrap: wrap; gap: 8px; align-items: center; }
+  .filters select, .filters input { font-size: 13px; padding: 4px; }
+  table { border-collapse: collapse; width: 100%; font-size: 13px; }
+  th, td { border-bottom: 1px solid #d1d9e0; padding: 6px 8px; text-align: left; vertical-align: top; }
+  th { background: #f6f8fa; position: sticky; top: 0; }
+  tr.issue { cursor: pointer; }
+  tr.issue:hover { background: #f6f8fa; }
+  tr.detail > td { background: #fbfcfd; }
+  .kind { border-radius: 10px; padding: 1px 8px; font-size: 12px; white-space: nowrap; }
+  .kind-mismatch { background: #ffebe9; color: #a40e26; }
+  .kind-known { background: #eef1f4; color: #59636e; }
+  .kind-desync { background: #ddf4ff; color: #0550ae; }
+  .kind-rust-error, .kind-sidecar-error, .kind-both-error, .kind-error-body { background: #fff8c5; color: #7d4e00; }
+  .tree, .tree ul { list-style: none; margin: 0; padding-left: 18px; font-family: ui-monospace, Menlo, Consolas, monospace; font-size: 12px; }
+  .tree summary { cursor: pointer; }
+  .has-diff > details > summary, .has-diff > span { color: #a40e26; }
+  .diff-node { background: #fff5f5; border-left: 3px solid #cf222e; padding: 2px 6px; margin: 2px 0; }
+  .diff-node.equivalent { background: #f6f8fa; border-left-color: #8c959f; }
+  .side { display: grid; grid-template-columns: 70px 1fr; gap: 2px 8px; margin-top: 4px; }
+  .label { color: #59636e; }
+  .key { color: #0550ae; }
+  .str { color: #0a3069; }
+  .num { color: #953800; }
+  .lit { color: #8250df; }
+  .type { font-weight: 600; }
+  .note { color: #59636e; font-style: italic; }
+  .hidden { display: none; }
+</style>
+</head>
+<body>
+<h1>{{TITLE}}</h1>
+<div class="meta" id="meta"></div>
+<div class="counts" id="counts"></div>
+<div class="filters">
+  <select id="f-endpoint"></select>
+  <select id="f-pallet"></select>
+  <select id="f-account"></select>
+  <select id="f-kind"></select>
+  <select id="f-type"></select>
+  <input id="f-text" type="search" placeholder="Filter by path, URL or message">
+  <span id="shown"></span>
+</div>
+<table>
+  <thead><tr><th>Block</th><th>Endpoint</th><th>Pallet</th><th>Account</th><th>Result</th><th>Diff types</th><th>Summary</th></tr></thead>
+  <tbody id="issues"></tbody>
+</table>
+<script type="application/json" id="data">{{DATA}}</script>
+<script>
+(function () {
+  var data = JSON.parse(document.getElementById('data').textContent);
+  var EQUIVALENT = ['EncodingOnly', 'Normalized', 'WithinTolerance'];
+
+  function el(tag, cls, text) {
+    var e = document.createElement(tag);
+    if (cls) e.className = cls;
+    if (text !== undefined) e.textContent = text;
+    return e;
+  }
+
+  document.getElementById('meta').textContent =
+    'Blocks ' + data.startBlock + ' - ' + data.endBlock + ' | Rust API: ' + data.rustUrl +
+    ' | Sidecar API: ' + data.sidecarUrl + ' | Generated ' + data.generated;
+  var c = data.counts;
+  [['Matched', c.matched], ['Mismatched (new)', c.mismatched], ['Known', c.known],
+   ['Rust errors', c.rust_errors], ['Sidecar errors', c.sidecar_errors],
+   ['Both errors (diff codes)', c.both_errors], ['Error body mismatches', c.error_body_mismatches],
+   ['Fork/desync', c.desyncs]].forEach(function (p) {
+    document.getElementById('counts').appendChild(el('span', null, p[0] + ': ' + p[1]));
+  });
+
+  data.issues.forEach(function (issue) {
+    issue.endpoint = data.endpoint;
+    issue.types = [];
+    issue.diffs.forEach(function (d) {
+      if (issue.types.indexOf(d.diff_type) < 0) issue.types.push(d.diff_type);
+    });
+  });
+
+  // Render a JSON value as an expandable tree
+  function jsonTree(value) {
+    if (value === null || typeof value !== 'object') {
+      var cls = typeof value === 'string' ? 'str' : typeof value === 'number' ? 'num' : 'lit';
+      return el('span', cls, JSON.stringify(value));
+    }
+    var isArray = Array.isArray(value);
+    var keys = Object.keys(value);
+    var details = el('details');
+    details.appendChild(el('summary', null, (isArray ? '[' : '{') + ' ' + keys.length +
+      (isArray ? ' items ]' : ' fields }')));
+    var list = el('ul');
+    keys.forEach(function (k) {
+      var li = el('li');
+      li.appendChild(el('span', 'key', isArray ? '[' + k + '] ' : '"' + k + '": '));
+      li.appendChild(jsonTree(value[k]));
+      list.appendChild(li);
+    });
+    details.appendChild(list);
+    return details;
+  }
+
+  function unescapePointer(s) { return s.replace(/~1/g, '/').replace(/~0/g, '~'); }
+
+  // Tree of the differing paths, built from the JSON Pointers of the diffs
+  function diffTree(diffs) {
+    var root = { children: {}, order: [], diffs: [] };
+    diffs.forEach(function (d) {
+      var node = root;
+      d.pointer.split('/').slice(1).map(unescapePointer).forEach(function (seg) {
+        if (!node.children[seg]) {
+          node.children[seg] = { children: {}, order: [], diffs: [] };
+          node.order.push(seg);
+        }
+        node = node.children[seg];
+      });
+      node.diffs.push(d);
+    });
+    var list = el('ul', 'tree');
+    renderNode(root, list);
+    return list;
+  }
+
+  function renderNode(node, list) {
+    node.diffs.forEach(function (d) {
+      var box = el('li', 'diff-node' + (EQUIVALENT.indexOf(d.diff_type) >= 0 ? ' equivalent' : ''));
+      box.appendChild(el('div', 'type', d.diff_type + (d.truncated ? ' (values truncated)' : '')));
+      box.appendChild(el('div', 'note', d.text));
+      var side = el('div', 'side');
+      side.appendChild(el('span', 'label', 'Rust'));
+      side.appendChild(d.rust === null || d.rust === undefined ? el('span', 'note', 'missing') : jsonTree(d.rust));
+      side.appendChild(el('span', 'label', 'Sidecar'));
+      side.appendChild(d.sidecar === null || d.sidecar === undefined ? el('span', 'note', 'missing') : jsonTree(d.sidecar));
+      box.appendChild(side);
+      list.appendChild(box);
+    });
+    node.order.forEach(function (seg) {
+      var child = node.children[seg];
+      var li = el('li', 'has-diff');
+      var details = el('details');
+      details.open = true;
+      details.appendChild(el('summary', null, seg));
+      var sub = el('ul');
+      renderNode(child, sub);
+      details.appendChild(sub);
+      li.appendChild(details);
+      list.appendChild(li);
+    });
+  }
+
+  function fillSelect(id, label, values) {
+    var select = document.getElementById(id);
+    select.appendChild(el('option', null, 'All ' + label)).value = '';
+    values.filter(function (v, i) { return v && values.indexOf(v) === i; }).sort().forEach(function (v) {
+      select.appendChild(el('option', null, v)).value = v;
+    });
+    select.addEventListener('change', render);
+  }
+  var all = function (f) { return data.issues.map(f); };
+  fillSelect('f-endpoint', 'endpoints', all(function (i) { return i.endpoint; }));
+  fillSelect('f-pallet', 'pallets', all(function (i) { return i.pallet; }));
+  fillSelect('f-account', 'accounts', all(function (i) { return i.account; }));
+  fillSelect('f-kind', 'results', all(function (i) { return i.kind; }));
+  fillSelect('f-type', 'diff types', [].concat.apply([], all(function (i) { return i.types; })));
+  document.getElementById('f-text').addEventListener('input', render);
+
+  function value(id) { return document.getElementById(id).value; }
+
+  function render() {
+    var tbody = document.getElementById('issues');
+    tbody.textContent = '';
+    var text = value('f-text').toLowerCase();
+    var shown = 0;
+    data.issues.forEach(function (issue) {
+      if (value('f-endpoint') && issue.endpoint !== value('f-endpoint')) return;
+      if (value('f-pallet') && issue.pallet !== value('f-pallet')) return;
+      if (value('f-account') && issue.account !== value('f-account')) return;
+      if (value('f-kind') && issue.kind !== value('f-kind')) return;
+      if (value('f-type') && issue.types.indexOf(value('f-type')) < 0) return;
+      if (text) {
+        var haystack = [issue.url, issue.message].concat(issue.diffs.map(function (d) { return d.text; }))
+          .join('\n').toLowerCase();
+        if (haystack.indexOf(text) < 0) return;
+      }
+      shown++;
+      var row = el('tr', 'issue');
+      [issue.block, issue.endpoint, issue.pallet || '', issue.account || ''].forEach(function (v) {
+        row.appendChild(el('td', null, String(v)));
+      });
+      var kind = el('td');
+      kind.appendChild(el('span', 'kind kind-' + issue.kind, issue.kind));
+      row.appendChild(kind);
+      row.appendChild(el('td', null, issue.types.join(', ')));
+      row.appendChild(el('td', null, issue.message));
+      var detail = el('tr', 'detail hidden');
+      var cell = el('td');
+      cell.colSpan = 7;
+      var link = el('a', null, issue.url);
+      link.href = issue.url;
+      cell.appendChild(link);
+      if (issue.diffs.length) cell.appendChild(diffTree(issue.diffs));
+      if (issue.omitted) cell.appendChild(el('div', 'note', '... and ' + issue.omitted + ' more diffs not included'));
+      detail.appendChild(cell);
+      row.addEventListener('click', function () { detail.classList.toggle('hidden'); });
+      tbody.appendChild(row);
+      tbody.appendChild(detail);
+    });
+    document.getElementById('shown').textContent = shown + ' of ' + data.issues.length + ' issues';
+  }
+  render();
+})();
+</script>
+</body>
+</html>
+"#;
@@ -1,107 +1,684 @@
 use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::cassette::Cassette;
 use crate::diff::{json_diff, json_equal, JsonDiff};
+use crate::headers::HeaderCheck;
+use crate::latency::ResponseSample;
+use crate::rules::DiffRules;
+use crate::schema::SchemaCheck;
+use crate::sidecar_cache::SidecarCache;
+use crate::sync::BlockIdentity;
+use crate::throttle::{ServerLimits, Throttle};
 
 /// Result of testing a block against both APIs
 #[derive(Debug)]
 pub enum TestResult {
-    /// Both APIs returned success with matching responses
-    Match,
-    /// Both APIs returned success but responses differ
+    /// Both APIs returned success with matching responses.
+    /// `equivalent` holds diffs between equivalent values (e.g. encoding-only).
+    Match { equivalent: Vec<JsonDiff> },
+    /// Both APIs returned success but responses differ.
+    /// The full responses are only kept when requested (for logs or spilling to disk).
     Mismatch {
-        rust_response: Value,
-        sidecar_response: Value,
+        rust_response: Option<Value>,
+        sidecar_response: Option<Value>,
         diffs: Vec<JsonDiff>,
+        /// Diffs beyond `DiffLimits::max_diffs`, counted but not stored
+        omitted_diffs: usize,
     },
     /// Rust API error
-    RustError(String),
+    RustError(ApiError),
     /// Sidecar API error
-    SidecarError(String),
+    SidecarError(ApiError),
     /// Both APIs returned errors
     BothError {
-        rust_error: String,
-        sidecar_error: String,
+        rust_error: ApiError,
+        sidecar_error: ApiError,
+    },
+    /// Both APIs returned the same error status but different error bodies
+    /// (only with `--error-parity`)
+    ErrorBodyMismatch {
+        rust_error: ApiError,
+        sidecar_error: ApiError,
+        diffs: Vec<JsonDiff>,
+        /// Diffs beyond `DiffLimits::max_diffs`, counted but not stored
+        omitted_diffs: usize,
+    },
+    /// The servers have different blocks at this height (fork or desynced node),
+    /// so the responses were not compared (only with `--verify-block-hashes`)
+    Desync {
+        rust: BlockIdentity,
+        sidecar: BlockIdentity,
     },
 }
 
-pub async fn get_latest_block(
-    client: &reqwest::Client,
-    base_url: &str,
-) -> Result<u32, Box<dyn std::error::Error>> {
-    let url = format!("{}/blocks/head", base_url);
+impl TestResult {
+    /// Whether the result counts as matched in the summaries. Both APIs erroring
+    /// counts as a match, even with different codes (those are also counted as
+    /// both-error issues).
+    pub fn counts_as_match(&self) -> bool {
+        matches!(
+            self,
+            TestResult::Match { .. } | TestResult::BothError { .. }
+        )
+    }
+}
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+/// Per-request timeout
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How transient failures (connect errors, timeouts, 429 and 502/503/504) are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 = no retries)
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on each further retry
+    pub base_delay: Duration,
+    /// Upper bound on a single wait, including one requested by `Retry-After`
+    pub max_delay: Duration,
+}
 
-    if !response.status().is_success() {
-        return Err(format!("Server returned HTTP {}: {}", response.status(), url).into());
+impl RetryPolicy {
+    /// Exponential backoff for the given retry (0-based), with jitter in [50%, 100%]
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << retry.min(16))
+            .min(self.max_delay);
+        delay.mul_f64(0.5 + random_fraction() / 2.0)
     }
+}
 
-    let json: Value = response
-        .json()
-        .await
-        .map_err(|e| format!("Invalid JSON response from {}: {}", url, e))?;
-
-    json["number"]
-        .as_str()
-        .and_then(|s| s.parse::<u32>().ok())
-        .ok_or_else(|| format!("Missing or invalid 'number' field in response from {}", url).into())
-}
-
-/// Fetch JSON from a URL, returning Ok(Value) on success or Err(String) on failure
-pub async fn fetch_json(client: &reqwest::Client, url: &str) -> Result<Value, String> {
-    match client.get(url).send().await {
-        Ok(response) => {
-            let status = response.status();
-            if status.is_success() {
-                response
-                    .json::<Value>()
-                    .await
-                    .map_err(|e| format!("Invalid JSON: {}", e))
-            } else {
-                Err(format!("HTTP {}", status))
+/// Uniform random number in [0, 1), seeded from the std hasher keys
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Which of the two APIs a request goes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Server {
+    Rust,
+    Sidecar,
+}
+
+/// HTTP client shared by all requests of a run
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    pub http: reqwest::Client,
+    pub retry: RetryPolicy,
+    rust: Arc<Throttle>,
+    sidecar: Arc<Throttle>,
+    cassette: Option<Arc<Cassette>>,
+    sidecar_cache: Option<Arc<SidecarCache>>,
+}
+
+impl ApiClient {
+    pub fn new(
+        retry: RetryPolicy,
+        rust_limits: ServerLimits,
+        sidecar_limits: ServerLimits,
+    ) -> Result<Self, reqwest::Error> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(ApiClient {
+            http,
+            retry,
+            rust: Arc::new(Throttle::new(rust_limits)),
+            sidecar: Arc::new(Throttle::new(sidecar_limits)),
+            cassette: None,
+            sidecar_cache: None,
+        })
+    }
+
+    /// Record every response into `cassette`, or replay from it instead of sending requests
+    pub fn with_cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Serve Sidecar requests from `cache` when possible, and store new successful responses
+    pub fn with_sidecar_cache(mut self, cache: Arc<SidecarCache>) -> Self {
+        self.sidecar_cache = Some(cache);
+        self
+    }
+
+    /// The same client without the Sidecar cache, for responses that change over time
+    pub fn without_sidecar_cache(&self) -> Self {
+        ApiClient {
+            sidecar_cache: None,
+            ..self.clone()
+        }
+    }
+
+    fn throttle(&self, server: Server) -> &Throttle {
+        match server {
+            Server::Rust => &self.rust,
+            Server::Sidecar => &self.sidecar,
+        }
+    }
+}
+
+/// Maximum length of an error body excerpt in error messages
+const BODY_EXCERPT_LEN: usize = 200;
+
+/// What went wrong with a request
+#[derive(Debug, Clone)]
+pub enum ErrorKind {
+    /// Could not connect (refused, DNS failure)
+    Connect(String),
+    /// No response within the request timeout
+    Timeout(String),
+    /// Connection dropped or another transport failure
+    Request(String),
+    /// Non-success HTTP status; `body` is parsed as JSON when possible, else kept as a string
+    Http {
+        status: u16,
+        headers: HeaderList,
+        body: Option<Value>,
+    },
+    /// Success status, but the body is not valid JSON
+    Decode { status: u16, message: String },
+}
+
+impl ErrorKind {
+    /// Short kind name ("connect", "timeout", "request", "http", "decode")
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Connect(_) => "connect",
+            ErrorKind::Timeout(_) => "timeout",
+            ErrorKind::Request(_) => "request",
+            ErrorKind::Http { .. } => "http",
+            ErrorKind::Decode { .. } => "decode",
+        }
+    }
+
+    /// HTTP status, if a response was received
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ErrorKind::Http { status, .. } | ErrorKind::Decode { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the failure is usually momentary and worth retrying
+    fn is_transient(&self) -> bool {
+        match self {
+            ErrorKind::Connect(_) | ErrorKind::Timeout(_) | ErrorKind::Request(_) => true,
+            ErrorKind::Http { status, .. } => matches!(status, 429 | 502 | 503 | 504),
+            ErrorKind::Decode { .. } => false,
+        }
+    }
+
+    /// Delay requested by a `Retry-After` header (seconds or an HTTP date)
+    fn retry_after(&self) -> Option<Duration> {
+        let ErrorKind::Http { headers, .. } = self else {
+            return None;
+        };
+        let value = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))?
+            .1
+            .trim();
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(Duration::from_secs(secs));
+        }
+        let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .ok()
+    }
+}
+
+/// A failed API request
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub kind: ErrorKind,
+    /// Time spent on the request, including retries
+    pub elapsed: Duration,
+    /// Retries made before giving up
+    pub retries: u32,
+}
+
+impl ApiError {
+    /// Kind and status, e.g. "http 500" or "timeout"; errors are grouped by this
+    pub fn category(&self) -> String {
+        match self.kind.status() {
+            Some(status) => format!("{} {}", self.kind.name(), status),
+            None => self.kind.name().to_string(),
+        }
+    }
+
+    /// Whether both errors are the same failure (same status, or same transport error)
+    pub fn same_as(&self, other: &ApiError) -> bool {
+        match (&self.kind, &other.kind) {
+            (ErrorKind::Http { status: a, .. }, ErrorKind::Http { status: b, .. }) => a == b,
+            _ => self.to_string() == other.to_string(),
+        }
+    }
+
+    /// Response body, if any
+    pub fn body(&self) -> Option<&Value> {
+        match self.kind {
+            ErrorKind::Http { ref body, .. } => body.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::Connect(message) | ErrorKind::Request(message) => {
+                write!(f, "Request failed: {}", message)?
             }
+            ErrorKind::Timeout(message) => write!(f, "Timed out: {}", message)?,
+            ErrorKind::Http { status, body, .. } => {
+                match reqwest::StatusCode::from_u16(*status) {
+                    Ok(code) => write!(f, "HTTP {}", code)?,
+                    Err(_) => write!(f, "HTTP {}", status)?,
+                }
+                if let Some(body) = body {
+                    let text = match body {
+                        Value::String(text) => text.clone(),
+                        _ => body.to_string(),
+                    };
+                    let excerpt: String = text.chars().take(BODY_EXCERPT_LEN).collect();
+                    let ellipsis = if excerpt.len() < text.len() {
+                        "..."
+                    } else {
+                        ""
+                    };
+                    write!(f, ": {}{}", excerpt, ellipsis)?;
+                }
+            }
+            ErrorKind::Decode { message, .. } => write!(f, "Invalid JSON: {}", message)?,
         }
-        Err(e) => Err(format!("Request failed: {}", e)),
+        if self.retries > 0 {
+            write!(f, " (after {} retries)", self.retries)?;
+        }
+        Ok(())
     }
 }
 
-/// Test a block by comparing responses from both APIs
+/// Error counts by side, kind and status over one scan
+#[derive(Debug, Clone, Default)]
+pub struct ErrorCounts {
+    /// (side, kind, status) -> count
+    counts: BTreeMap<(&'static str, &'static str, Option<u16>), u32>,
+}
+
+impl ErrorCounts {
+    /// Count an error on one side ("rust" or "sidecar")
+    pub fn add(&mut self, side: &'static str, error: &ApiError) {
+        *self
+            .counts
+            .entry((side, error.kind.name(), error.kind.status()))
+            .or_default() += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// (side, kind, status, count), ordered by side, kind and status
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&'static str, &'static str, Option<u16>, u32)> + '_ {
+        self.counts
+            .iter()
+            .map(|((side, kind, status), count)| (*side, *kind, *status, *count))
+    }
+
+    /// One-line breakdown, e.g. "rust http 500 x3, sidecar timeout x1"
+    pub fn summary(&self) -> String {
+        self.iter()
+            .map(|(side, kind, status, count)| match status {
+                Some(status) => format!("{} {} {} x{}", side, kind, status, count),
+                None => format!("{} {} x{}", side, kind, count),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Response headers as (lowercase name, value) pairs, in received order
+pub type HeaderList = Vec<(String, String)>;
+
+/// A successful response
+#[derive(Debug)]
+pub struct ApiResponse {
+    pub body: Value,
+    pub headers: HeaderList,
+    /// Time and body size of the attempt that succeeded
+    pub sample: ResponseSample,
+}
+
+impl ApiResponse {
+    /// Mark a response that was not fetched now, so its stored time is not measured
+    fn cached(mut self) -> Self {
+        self.sample.cached = true;
+        self
+    }
+}
+
+fn header_list(headers: &reqwest::header::HeaderMap) -> HeaderList {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+/// A response as received, before its body is parsed (what a cassette stores)
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    pub status: u16,
+    pub headers: HeaderList,
+    pub body: Vec<u8>,
+    /// Time until the full body was received
+    pub elapsed: Duration,
+}
+
+impl RawResponse {
+    /// Parse the body: JSON on success, otherwise an `Http` error whose body is
+    /// kept as JSON when possible, else as a string
+    pub fn parse(&self) -> Result<ApiResponse, ErrorKind> {
+        let (status, headers) = (self.status, self.headers.clone());
+        if (200..300).contains(&status) {
+            let sample = ResponseSample {
+                elapsed: self.elapsed,
+                bytes: self.body.len(),
+                cached: false,
+            };
+            let body =
+                serde_json::from_slice::<Value>(&self.body).map_err(|e| ErrorKind::Decode {
+                    status,
+                    message: e.to_string(),
+                })?;
+            return Ok(ApiResponse {
+                body,
+                headers,
+                sample,
+            });
+        }
+
+        let body = (!self.body.is_empty()).then(|| {
+            serde_json::from_slice(&self.body)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&self.body).into_owned()))
+        });
+        Err(ErrorKind::Http {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+async fn fetch_once(client: &reqwest::Client, url: &str) -> Result<RawResponse, ErrorKind> {
+    let started = Instant::now();
+    let response = client.get(url).send().await.map_err(transport_error)?;
+    let status = response.status().as_u16();
+    let headers = header_list(response.headers());
+    let body = match response.bytes().await {
+        Ok(bytes) => bytes.to_vec(),
+        // A success body must arrive in full; an error body is best effort
+        Err(e) if (200..300).contains(&status) => return Err(transport_error(e)),
+        Err(_) => Vec::new(),
+    };
+    Ok(RawResponse {
+        status,
+        headers,
+        body,
+        elapsed: started.elapsed(),
+    })
+}
+
+fn transport_error(e: reqwest::Error) -> ErrorKind {
+    if e.is_connect() {
+        ErrorKind::Connect(e.to_string())
+    } else if e.is_timeout() {
+        ErrorKind::Timeout(e.to_string())
+    } else {
+        ErrorKind::Request(e.to_string())
+    }
+}
+
+/// Fetch a JSON response from a URL, retrying transient failures per the client's
+/// `RetryPolicy`. Every attempt waits for the server's request limits first.
+/// When recording, the final attempt is stored in the cassette; when replaying,
+/// the recorded outcome is returned without any request. Sidecar requests are
+/// served from the Sidecar cache when it holds the URL, and fill it otherwise.
+/// Returns the result and the number of retries it took.
+pub async fn fetch_with_retries(
+    client: &ApiClient,
+    server: Server,
+    url: &str,
+) -> (Result<ApiResponse, ApiError>, u32) {
+    let started = Instant::now();
+    if let Some(cassette) = client.cassette.as_deref().filter(|c| c.is_replay()) {
+        let result = cassette
+            .replay_attempt(url)
+            .and_then(|raw| raw.parse())
+            .map(ApiResponse::cached)
+            .map_err(|kind| ApiError {
+                kind,
+                elapsed: started.elapsed(),
+                retries: 0,
+            });
+        return (result, 0);
+    }
+
+    let cache = client
+        .sidecar_cache
+        .as_deref()
+        .filter(|_| server == Server::Sidecar);
+    if let Some(raw) = cache.and_then(|cache| cache.get(url)) {
+        let attempt: Result<RawResponse, ErrorKind> = Ok(raw);
+        if let Some(ref cassette) = client.cassette {
+            cassette.record_attempt(url, &attempt);
+        }
+        let result = attempt
+            .and_then(|raw| raw.parse())
+            .map(ApiResponse::cached)
+            .map_err(|kind| ApiError {
+                kind,
+                elapsed: started.elapsed(),
+                retries: 0,
+            });
+        return (result, 0);
+    }
+
+    let mut retries = 0;
+    loop {
+        let permit = client.throttle(server).acquire().await;
+        let attempt = fetch_once(&client.http, url).await;
+        drop(permit);
+        let result = match attempt {
+            Ok(ref raw) => raw.parse(),
+            Err(ref kind) => Err(kind.clone()),
+        };
+        match result {
+            Err(kind) if kind.is_transient() && retries < client.retry.max_retries => {
+                let delay = match kind.retry_after() {
+                    Some(delay) => delay.min(client.retry.max_delay),
+                    None => client.retry.backoff(retries),
+                };
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            }
+            result => {
+                if let Some(ref cassette) = client.cassette {
+                    cassette.record_attempt(url, &attempt);
+                }
+                if let (Some(cache), Ok(raw)) = (cache, &attempt) {
+                    cache.put(url, raw);
+                }
+                let result = result.map_err(|kind| ApiError {
+                    kind,
+                    elapsed: started.elapsed(),
+                    retries,
+                });
+                return (result, retries);
+            }
+        }
+    }
+}
+
+/// Fetch JSON from a URL, returning Ok(Value) on success or the `ApiError` on failure
+pub async fn fetch_json(client: &ApiClient, server: Server, url: &str) -> Result<Value, ApiError> {
+    fetch_with_retries(client, server, url)
+        .await
+        .0
+        .map(|response| response.body)
+}
+
+/// Retries and successful-response samples of one request pair
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestStats {
+    /// Retries both requests needed
+    pub retries: u32,
+    pub rust: Option<ResponseSample>,
+    pub sidecar: Option<ResponseSample>,
+}
+
+/// Test a block by comparing responses from both APIs.
+/// The full responses are dropped after diffing unless `keep_responses` is set.
+/// With a `schema`, Sidecar responses feed schema inference and Rust responses are
+/// validated against the stored schema, whether or not Sidecar responded.
+/// With `headers`, the selected headers of responses with the same status are compared.
+/// Also returns the retries and latency / size samples of both requests.
 pub async fn test_block_compare(
-    client: reqwest::Client,
+    client: ApiClient,
     rust_url: String,
     sidecar_url: String,
     block_num: u64,
-) -> (u64, TestResult) {
+    rules: Arc<DiffRules>,
+    keep_responses: bool,
+    schema: Option<Arc<SchemaCheck>>,
+    headers: Option<Arc<HeaderCheck>>,
+) -> (u64, TestResult, RequestStats) {
     // Fetch from both APIs concurrently
-    let (rust_result, sidecar_result) = tokio::join!(
-        fetch_json(&client, &rust_url),
-        fetch_json(&client, &sidecar_url)
+    let ((rust_result, rust_retries), (sidecar_result, sidecar_retries)) = tokio::join!(
+        fetch_with_retries(&client, Server::Rust, &rust_url),
+        fetch_with_retries(&client, Server::Sidecar, &sidecar_url)
     );
+    let stats = RequestStats {
+        retries: rust_retries + sidecar_retries,
+        rust: rust_result.as_ref().ok().map(|response| response.sample),
+        sidecar: sidecar_result.as_ref().ok().map(|response| response.sample),
+    };
+
+    if let Some(headers) = headers {
+        if let Some((rust_headers, sidecar_headers)) =
+            comparable_headers(&rust_result, &sidecar_result)
+        {
+            headers.compare(block_num, &rust_url, rust_headers, sidecar_headers);
+        }
+    }
+    let rust_result = rust_result.map(|response| response.body);
+    let sidecar_result = sidecar_result.map(|response| response.body);
+
+    if let Some(schema) = schema {
+        if let Ok(ref sidecar_json) = sidecar_result {
+            schema.observe(sidecar_json);
+        }
+        if let Ok(ref rust_json) = rust_result {
+            schema.validate(block_num, &rust_url, rust_json, &rules);
+        }
+    }
 
     let result = match (rust_result, sidecar_result) {
         (Ok(rust_json), Ok(sidecar_json)) => {
-            if json_equal(&rust_json, &sidecar_json) {
-                TestResult::Match
+            if json_equal(&rust_json, &sidecar_json, &rules) {
+                TestResult::Match {
+                    equivalent: Vec::new(),
+                }
             } else {
-                let diffs = json_diff(&rust_json, &sidecar_json);
+                let outcome = json_diff(&rust_json, &sidecar_json, &rules);
+                if outcome.hard_diffs == 0 {
+                    return (
+                        block_num,
+                        TestResult::Match {
+                            equivalent: outcome.diffs,
+                        },
+                        stats,
+                    );
+                }
                 TestResult::Mismatch {
-                    rust_response: rust_json,
-                    sidecar_response: sidecar_json,
-                    diffs,
+                    rust_response: keep_responses.then_some(rust_json),
+                    sidecar_response: keep_responses.then_some(sidecar_json),
+                    diffs: outcome.diffs,
+                    omitted_diffs: outcome.omitted,
                 }
             }
         }
         (Err(rust_err), Ok(_)) => TestResult::RustError(rust_err),
         (Ok(_), Err(sidecar_err)) => TestResult::SidecarError(sidecar_err),
-        (Err(rust_err), Err(sidecar_err)) => TestResult::BothError {
-            rust_error: rust_err,
-            sidecar_error: sidecar_err,
-        },
+        (Err(rust_err), Err(sidecar_err)) => compare_errors(rust_err, sidecar_err, &rules),
     };
 
-    (block_num, result)
+    (block_num, result, stats)
+}
+
+/// Classify two errors; with error parity enabled, errors with the same status
+/// whose bodies differ beyond the error-body rules are an `ErrorBodyMismatch`
+fn compare_errors(rust_error: ApiError, sidecar_error: ApiError, rules: &DiffRules) -> TestResult {
+    if let Some(error_rules) = rules.error_body_rules() {
+        if let (true, Some(rust_body), Some(sidecar_body)) = (
+            rust_error.same_as(&sidecar_error),
+            rust_error.body(),
+            sidecar_error.body(),
+        ) {
+            let outcome = json_diff(rust_body, sidecar_body, error_rules);
+            if outcome.hard_diffs > 0 {
+                return TestResult::ErrorBodyMismatch {
+                    rust_error,
+                    sidecar_error,
+                    diffs: outcome.diffs,
+                    omitted_diffs: outcome.omitted,
+                };
+            }
+        }
+    }
+    TestResult::BothError {
+        rust_error,
+        sidecar_error,
+    }
+}
+
+/// Headers of both responses, when both succeeded or failed with the same HTTP status
+fn comparable_headers<'a>(
+    rust: &'a Result<ApiResponse, ApiError>,
+    sidecar: &'a Result<ApiResponse, ApiError>,
+) -> Option<(&'a HeaderList, &'a HeaderList)> {
+    match (rust, sidecar) {
+        (Ok(rust), Ok(sidecar)) => Some((&rust.headers, &sidecar.headers)),
+        (Err(rust), Err(sidecar)) => match (&rust.kind, &sidecar.kind) {
+            (
+                ErrorKind::Http {
+                    status: rust_status,
+                    headers: rust_headers,
+                    ..
+                },
+                ErrorKind::Http {
+                    status: sidecar_status,
+                    headers: sidecar_headers,
+                    ..
+                },
+            ) if rust_status == sidecar_status => Some((rust_headers, sidecar_headers)),
+            _ => None,
+        },
+        _ => None,
+    }
 }
@@ -0,0 +1,221 @@
+//! Per-request latency and response size, compared between the two servers.
+//!
+//! Every successful request records its wall-clock time (of the attempt that
+//! succeeded, without retry backoff) and its body size. Samples are collected per
+//! endpoint, pallet and account and summarized as p50 / p90 / p99 / max, so every
+//! correctness scan doubles as a lightweight performance comparison. Responses
+//! served from the Sidecar cache or a replayed cassette carry a stored time, so
+//! they are only counted, not measured.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Time and body size of one successful request
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseSample {
+    pub elapsed: Duration,
+    pub bytes: usize,
+    /// Served from the Sidecar cache or a replayed cassette (`elapsed` is the stored time)
+    pub cached: bool,
+}
+
+/// Samples of both servers collected over one scope (endpoint, pallet or account)
+#[derive(Debug, Clone, Default)]
+pub struct LatencySamples {
+    rust: Vec<ResponseSample>,
+    sidecar: Vec<ResponseSample>,
+    /// Cached responses per side, left out of the samples
+    rust_cached: u32,
+    sidecar_cached: u32,
+}
+
+impl LatencySamples {
+    /// Add the samples of one request pair (either side may have failed)
+    pub fn add(&mut self, rust: Option<ResponseSample>, sidecar: Option<ResponseSample>) {
+        for (sample, samples, cached) in [
+            (rust, &mut self.rust, &mut self.rust_cached),
+            (sidecar, &mut self.sidecar, &mut self.sidecar_cached),
+        ] {
+            match sample {
+                Some(sample) if sample.cached => *cached += 1,
+                Some(sample) => samples.push(sample),
+                None => {}
+            }
+        }
+    }
+
+    /// Add all samples of another scope
+    pub fn extend(&mut self, other: &LatencySamples) {
+        self.rust.extend_from_slice(&other.rust);
+        self.sidecar.extend_from_slice(&other.sidecar);
+        self.rust_cached += other.rust_cached;
+        self.sidecar_cached += other.sidecar_cached;
+    }
+
+    pub fn summary(&self) -> LatencyComparison {
+        LatencyComparison {
+            rust: LatencySummary {
+                cached: self.rust_cached,
+                ..LatencySummary::from_samples(&self.rust)
+            },
+            sidecar: LatencySummary {
+                cached: self.sidecar_cached,
+                ..LatencySummary::from_samples(&self.sidecar)
+            },
+        }
+    }
+}
+
+/// Latency percentiles and body sizes of one server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    /// Successful requests measured
+    pub requests: u32,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub avg_bytes: u64,
+    pub max_bytes: u64,
+    /// Successful responses served from the Sidecar cache or a replayed cassette,
+    /// not measured
+    #[serde(default)]
+    pub cached: u32,
+}
+
+impl LatencySummary {
+    fn from_samples(samples: &[ResponseSample]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut millis: Vec<f64> = samples
+            .iter()
+            .map(|s| s.elapsed.as_secs_f64() * 1000.0)
+            .collect();
+        millis.sort_by(|a, b| a.total_cmp(b));
+        let total_bytes: u64 = samples.iter().map(|s| s.bytes as u64).sum();
+        LatencySummary {
+            requests: samples.len() as u32,
+            p50_ms: percentile(&millis, 0.50),
+            p90_ms: percentile(&millis, 0.90),
+            p99_ms: percentile(&millis, 0.99),
+            max_ms: millis[millis.len() - 1],
+            avg_bytes: total_bytes / samples.len() as u64,
+            max_bytes: samples.iter().map(|s| s.bytes as u64).max().unwrap_or(0),
+            cached: 0,
+        }
+    }
+}
+
+/// Rust vs Sidecar latency over one scope
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyComparison {
+    pub rust: LatencySummary,
+    pub sidecar: LatencySummary,
+}
+
+impl LatencyComparison {
+    /// Whether no request succeeded on either side
+    pub fn is_empty(&self) -> bool {
+        self.rust.requests == 0 && self.sidecar.requests == 0
+    }
+
+    /// How the p50 latencies compare, e.g. "Rust p50 is 2.10x faster"
+    pub fn verdict(&self) -> Option<String> {
+        let (rust, sidecar) = (self.rust.p50_ms, self.sidecar.p50_ms);
+        if self.rust.requests == 0 || self.sidecar.requests == 0 || rust <= 0.0 || sidecar <= 0.0 {
+            return None;
+        }
+        Some(if rust <= sidecar {
+            format!("Rust p50 is {:.2}x faster", sidecar / rust)
+        } else {
+            format!("Rust p50 is {:.2}x slower", rust / sidecar)
+        })
+    }
+
+    /// Console lines of the "Rust vs Sidecar latency" section
+    pub fn summary_lines(&self) -> Vec<String> {
+        let cached = self.rust.cached + self.sidecar.cached;
+        if self.is_empty() && cached == 0 {
+            return Vec::new();
+        }
+        let mut lines = vec![
+            "\nRust vs Sidecar latency:".to_string(),
+            format!(
+                "  {:<8} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                "", "Requests", "p50", "p90", "p99", "max", "avg size", "max size"
+            ),
+        ];
+        for (label, side) in [("Rust", &self.rust), ("Sidecar", &self.sidecar)] {
+            lines.push(format!(
+                "  {:<8} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                label,
+                side.requests,
+                format_ms(side.p50_ms),
+                format_ms(side.p90_ms),
+                format_ms(side.p99_ms),
+                format_ms(side.max_ms),
+                format_bytes(side.avg_bytes),
+                format_bytes(side.max_bytes)
+            ));
+        }
+        if cached > 0 {
+            lines.push(format!(
+                "  Not measured: {} Rust and {} Sidecar response(s) served from the Sidecar cache or a cassette",
+                self.rust.cached, self.sidecar.cached
+            ));
+        }
+        if let Some(verdict) = self.verdict() {
+            lines.push(format!("  {}", verdict));
+        }
+        lines
+    }
+
+    /// Header of a markdown latency table whose first column is `scope`
+    pub fn markdown_header(scope: &str) -> String {
+        format!(
+            "| {} | Rust p50 | Rust p90 | Rust p99 | Rust max | Sidecar p50 | Sidecar p90 | Sidecar p99 | Sidecar max | Rust avg size | Sidecar avg size |\n\
+             |{}|----------|----------|----------|----------|-------------|-------------|-------------|-------------|---------------|------------------|\n",
+            scope,
+            "-".repeat(scope.len() + 2)
+        )
+    }
+
+    /// One row of a markdown latency table
+    pub fn markdown_row(&self, scope: &str) -> String {
+        format!(
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            scope,
+            format_ms(self.rust.p50_ms),
+            format_ms(self.rust.p90_ms),
+            format_ms(self.rust.p99_ms),
+            format_ms(self.rust.max_ms),
+            format_ms(self.sidecar.p50_ms),
+            format_ms(self.sidecar.p90_ms),
+            format_ms(self.sidecar.p99_ms),
+            format_ms(self.sidecar.max_ms),
+            format_bytes(self.rust.avg_bytes),
+            format_bytes(self.sidecar.avg_bytes)
+        )
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn format_ms(ms: f64) -> String {
+    format!("{:.1}ms", ms)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1}MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1}KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
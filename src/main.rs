@@ -1,25 +1,50 @@
+#![allow(clippy::too_many_arguments)]
+
 use clap::Parser;
 use std::error::Error;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
+mod baseline;
+mod cassette;
 mod chains;
+mod cluster;
 mod coverage;
 mod diff;
 mod endpoints;
+mod headers;
+mod html_report;
 mod http;
+mod latency;
 mod memory;
 mod query_params;
 mod reporting;
+mod rules;
 mod scanner;
+mod schema;
+mod sidecar_cache;
+mod sync;
+mod throttle;
+mod viewer;
 
+use baseline::BaselineFile;
+use cassette::Cassette;
 use chains::Chain;
 use coverage::CoverageData;
+use diff::DiffLimits;
 use endpoints::EndpointType;
-use http::get_latest_block;
+use headers::HeaderCheck;
+use http::{ApiClient, RetryPolicy, Server};
+use rules::{NullPolicy, RulesFile};
 use scanner::{
     scan_account_endpoint, scan_block_endpoint, scan_pallet_endpoint, scan_runtime_endpoint,
+    spill_responses,
 };
+use schema::{SchemaCheck, SchemaFile};
+use sidecar_cache::SidecarCache;
+use sync::{get_latest_block, verify_block_identities, BlockHashCheck, FinalityGate};
+use throttle::ServerLimits;
 
 /// Polkadot REST API checker - test endpoint responses across block ranges
 #[derive(Parser, Debug)]
@@ -37,13 +62,30 @@ struct Args {
     #[arg(short, long, default_value_t = 0)]
     start: u32,
 
-    /// End block number (default: latest block)
+    /// End block number (default: the lower finalized head of the two servers)
     #[arg(short, long)]
     end: Option<u32>,
 
-    /// Batch size for concurrent requests
-    #[arg(short, long, default_value_t = 100)]
-    batch_size: u32,
+    /// Warn when the finalized heads of the two servers are more than this many blocks apart
+    #[arg(long, default_value_t = sync::DEFAULT_MAX_HEAD_GAP)]
+    max_head_gap: u32,
+
+    /// Check every block on both servers via /blocks/{n}/header before comparing it, and
+    /// report blocks the servers disagree on as fork/desync instead of mismatch
+    #[arg(long)]
+    verify_block_hashes: bool,
+
+    /// Never scan above the finalized head of both servers, even when --end is higher
+    #[arg(long)]
+    finalized_only: bool,
+
+    /// Seconds to wait for unfinalized blocks to become final before re-checking their results
+    #[arg(long, default_value_t = 120)]
+    finality_timeout: u64,
+
+    /// Request pairs in flight; the next pair starts as soon as any pair completes
+    #[arg(short = 'b', long, alias = "batch-size", default_value_t = 100)]
+    concurrency: usize,
 
     /// Base URL for the new Rust API
     #[arg(short = 'u', long, default_value = "http://localhost:8080/v1")]
@@ -53,10 +95,6 @@ struct Args {
     #[arg(long, default_value = "http://localhost:8045")]
     sidecar_url: String,
 
-    /// Delay between batches in milliseconds
-    #[arg(short, long, default_value_t = 100)]
-    delay: u64,
-
     /// Filter to specific pallet name (case-insensitive, only for pallet endpoints)
     #[arg(short, long)]
     pallet: Option<String>,
@@ -97,6 +135,147 @@ struct Args {
     /// Path to substrate-api-sidecar git repo (for commit tracking)
     #[arg(long, default_value = "../substrate-api-sidecar")]
     sidecar_repo_path: String,
+
+    /// Path to a JSON normalization rules file (per-path ignores and normalizations)
+    #[arg(long)]
+    rules: Option<String>,
+
+    /// Compare strings case-sensitively (fold case only where an `ignore-case` rule applies)
+    #[arg(long)]
+    strict: bool,
+
+    /// How `null` compares against a missing field: strict (missing), equal (ignored), or report (separate NullVsMissing diff)
+    #[arg(long, default_value = "strict")]
+    null_policy: String,
+
+    /// When both APIs return the same error status, also diff the error bodies
+    /// (rules with `"target": "error-body"` apply; `stack` is ignored)
+    #[arg(long)]
+    error_parity: bool,
+
+    /// Path to a JSON baseline of known differences (counted as "known" instead of "mismatched")
+    #[arg(long)]
+    baseline: Option<String>,
+
+    /// Directory to write a JSON Patch (RFC 6902, Rust -> Sidecar) per new mismatch
+    #[arg(long)]
+    patch_dir: Option<String>,
+
+    /// Maximum number of diffs stored per response pair (0 = unlimited); further diffs are only counted
+    #[arg(long, default_value_t = 1000)]
+    max_diffs: usize,
+
+    /// Diff values larger than this many bytes are kept as a short preview (0 = unlimited)
+    #[arg(long, default_value_t = 4096)]
+    max_value_size: usize,
+
+    /// Directory to write the full Rust and Sidecar responses of each new mismatch
+    #[arg(long)]
+    spill_dir: Option<String>,
+
+    /// Show a side-by-side diff of the endpoint at this block and exit (uses --pallet / --account)
+    #[arg(long)]
+    show: Option<u32>,
+
+    /// Show a side-by-side diff of responses saved by --spill-dir and exit
+    /// (path prefix, e.g. spill/polkadot_block_1000 for polkadot_block_1000.rust.json + .sidecar.json)
+    #[arg(long)]
+    show_saved: Option<String>,
+
+    /// Account address for --show on account endpoints (default: first test account of the chain)
+    #[arg(long)]
+    account: Option<String>,
+
+    /// Equal lines shown around each change in --show / --show-saved
+    #[arg(long, default_value_t = 3)]
+    context: usize,
+
+    /// Expand equal subtrees in --show / --show-saved instead of collapsing them
+    #[arg(long)]
+    show_equal: bool,
+
+    /// Infer the response schema from Sidecar responses and save it to --schema-file
+    #[arg(long)]
+    infer_schema: bool,
+
+    /// Validate Rust responses against the schema stored in --schema-file; with
+    /// --infer-schema, against the schema inferred in this run, at the end of the scan
+    #[arg(long)]
+    validate_schema: bool,
+
+    /// Path to the inferred response schemas file
+    #[arg(long, default_value = "reports/schemas.json")]
+    schema_file: String,
+
+    /// Compare response headers (content-type, content-encoding, cache-control, vary and CORS)
+    #[arg(long)]
+    compare_headers: bool,
+
+    /// Additional response headers to compare (comma-separated, implies --compare-headers)
+    /// Example: --custom-headers x-api-version,etag
+    #[arg(long)]
+    custom_headers: Option<String>,
+
+    /// Retries per request on connect errors, timeouts, HTTP 429 and 502/503/504 (0 = no retries)
+    #[arg(long, default_value_t = 2)]
+    retries: u32,
+
+    /// Backoff before the first retry in ms, doubled on each further retry (with jitter)
+    #[arg(long, default_value_t = 500)]
+    retry_delay_ms: u64,
+
+    /// Maximum wait between retries in ms (also caps Retry-After)
+    #[arg(long, default_value_t = 10000)]
+    retry_max_delay_ms: u64,
+
+    /// Maximum concurrent requests to the Rust API (default: bounded by --concurrency)
+    #[arg(long)]
+    rust_concurrency: Option<usize>,
+
+    /// Maximum concurrent requests to Sidecar (default: bounded by --concurrency)
+    #[arg(long)]
+    sidecar_concurrency: Option<usize>,
+
+    /// Maximum requests per second to the Rust API, retries included (default: unlimited)
+    #[arg(long)]
+    rust_rps: Option<f64>,
+
+    /// Maximum requests per second to Sidecar, retries included (default: unlimited)
+    #[arg(long)]
+    sidecar_rps: Option<f64>,
+
+    /// Record every response of both servers into this compressed cassette (appends)
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<String>,
+
+    /// Replay responses from a cassette made with --record instead of querying the servers
+    #[arg(long)]
+    replay: Option<String>,
+
+    /// Serve Sidecar responses of block-scoped scans from a persistent cache keyed by
+    /// Sidecar commit, chain and URL, and store new ones in it
+    #[arg(long)]
+    sidecar_cache: bool,
+
+    /// Directory of the Sidecar response cache
+    #[arg(long, default_value = "reports/sidecar-cache")]
+    sidecar_cache_dir: String,
+
+    /// Size cap of the Sidecar response cache in MiB (least recently used entries are evicted)
+    #[arg(long, default_value_t = 2048)]
+    sidecar_cache_max_mb: u64,
+
+    /// Sidecar commit used as the cache key (default: detected from --sidecar-repo-path)
+    #[arg(long)]
+    sidecar_commit: Option<String>,
+
+    /// Show the entries and size of the Sidecar cache per commit and chain, and exit
+    #[arg(long)]
+    sidecar_cache_stats: bool,
+
+    /// Remove cached Sidecar responses and exit: "all", a Sidecar commit, or a chain
+    #[arg(long)]
+    clear_sidecar_cache: Option<String>,
 }
 
 #[tokio::main]
@@ -105,6 +284,29 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let cli_command: String = std::env::args().collect::<Vec<_>>().join(" ");
 
     let args = Args::parse();
+    let retry_policy = RetryPolicy {
+        max_retries: args.retries,
+        base_delay: Duration::from_millis(args.retry_delay_ms),
+        max_delay: Duration::from_millis(args.retry_max_delay_ms),
+    };
+    for rate in [args.rust_rps, args.sidecar_rps].into_iter().flatten() {
+        if !(rate > 0.0 && rate.is_finite()) {
+            let e = format!(
+                "Invalid requests-per-second limit '{}': must be a positive number",
+                rate
+            );
+            eprintln!("Error: {}", e);
+            return Err(e.into());
+        }
+    }
+    let rust_limits = ServerLimits {
+        concurrency: args.rust_concurrency,
+        rate: args.rust_rps,
+    };
+    let sidecar_limits = ServerLimits {
+        concurrency: args.sidecar_concurrency,
+        rate: args.sidecar_rps,
+    };
     let coverage_path = Path::new(&args.coverage_file);
 
     // Load existing coverage data
@@ -116,6 +318,47 @@ async fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
+    // Sidecar cache maintenance: show statistics or invalidate, then exit
+    let cache_dir = Path::new(&args.sidecar_cache_dir);
+    if let Some(ref target) = args.clear_sidecar_cache {
+        let (entries, bytes) = sidecar_cache::clear(cache_dir, target).map_err(|e| {
+            eprintln!("Error: {}", e);
+            e
+        })?;
+        println!(
+            "Removed {} cached Sidecar responses ({}) for '{}'",
+            entries,
+            sidecar_cache::format_size(bytes),
+            target
+        );
+        sidecar_cache::print_usage(cache_dir);
+        return Ok(());
+    }
+    if args.sidecar_cache_stats {
+        sidecar_cache::print_usage(cache_dir);
+        return Ok(());
+    }
+
+    // Open the cassette: record every response, or replay instead of querying the servers
+    let cassette = match (&args.record, &args.replay) {
+        (Some(path), _) => Some(Cassette::record(Path::new(path))),
+        (None, Some(path)) => Some(Cassette::replay(Path::new(path))),
+        (None, None) => None,
+    }
+    .transpose()
+    .map_err(|e| {
+        eprintln!("Error: {}", e);
+        e
+    })?
+    .map(Arc::new);
+    let new_client = || -> Result<ApiClient, reqwest::Error> {
+        let client = ApiClient::new(retry_policy, rust_limits, sidecar_limits)?;
+        Ok(match cassette {
+            Some(ref cassette) => client.with_cassette(Arc::clone(cassette)),
+            None => client,
+        })
+    };
+
     // Parse the chain argument
     let chain: Chain = args.chain.parse().map_err(|e: String| {
         eprintln!("Error: {}", e);
@@ -141,11 +384,119 @@ async fn main() -> Result<(), Box<dyn Error>> {
         None => Vec::new(),
     };
 
+    // Parse the null-vs-missing policy
+    let null_policy: NullPolicy = args.null_policy.parse().map_err(|e: String| {
+        eprintln!("Error: {}", e);
+        e
+    })?;
+
+    // Load normalization rules and select the ones scoped to this chain + endpoint
+    let rules_file = match &args.rules {
+        Some(path) => RulesFile::load(Path::new(path)).map_err(|e| {
+            eprintln!("Error: {}", e);
+            e
+        })?,
+        None => RulesFile::default(),
+    };
+    let diff_limits = DiffLimits {
+        max_diffs: args.max_diffs,
+        max_value_bytes: args.max_value_size,
+    };
+    let mut diff_rules = rules_file.for_scope(
+        &chain,
+        &endpoint_type,
+        args.strict,
+        null_policy,
+        diff_limits,
+    );
+    if args.error_parity {
+        diff_rules = diff_rules.with_error_body_rules(rules_file.for_error_bodies(
+            &chain,
+            &endpoint_type,
+            args.strict,
+            null_policy,
+            diff_limits,
+        ));
+    }
+    let diff_rules = Arc::new(diff_rules);
+
+    // Show a single response pair side by side and exit
+    if args.show.is_some() || args.show_saved.is_some() {
+        let options = viewer::ViewOptions {
+            context: args.context,
+            expand_equal: args.show_equal,
+        };
+        let (rust_label, sidecar_label, rust_json, sidecar_json) = match args.show_saved {
+            Some(ref prefix) => {
+                let load = |suffix: &str| -> Result<(String, serde_json::Value), Box<dyn Error>> {
+                    let path = format!("{}.{}.json", prefix, suffix);
+                    let content = std::fs::read_to_string(&path)
+                        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                    let json = serde_json::from_str(&content)
+                        .map_err(|e| format!("Invalid JSON in {}: {}", path, e))?;
+                    Ok((path, json))
+                };
+                let (rust_path, rust_json) = load("rust")?;
+                let (sidecar_path, sidecar_json) = load("sidecar")?;
+                (rust_path, sidecar_path, rust_json, sidecar_json)
+            }
+            None => {
+                let block = args.show;
+                let account = match args.account {
+                    Some(ref address) => Some(address.as_str()),
+                    None if endpoint_type.requires_account() => {
+                        chain.test_accounts().first().map(|a| a.address)
+                    }
+                    None => None,
+                };
+                let path = endpoint_type.path_with_account_params(
+                    args.pallet.as_deref(),
+                    block.filter(|_| endpoint_type.requires_block()),
+                    account,
+                    &query_params,
+                );
+                let rust_api_url = format!("{}{}", args.url, path);
+                let sidecar_api_url = format!("{}{}", args.sidecar_url, path);
+                let client = new_client()?;
+                let (rust_result, sidecar_result) = tokio::join!(
+                    http::fetch_json(&client, Server::Rust, &rust_api_url),
+                    http::fetch_json(&client, Server::Sidecar, &sidecar_api_url)
+                );
+                let rust_json =
+                    rust_result.map_err(|e| format!("Rust API error [{}]: {}", rust_api_url, e))?;
+                let sidecar_json = sidecar_result
+                    .map_err(|e| format!("Sidecar error [{}]: {}", sidecar_api_url, e))?;
+                (rust_api_url, sidecar_api_url, rust_json, sidecar_json)
+            }
+        };
+        viewer::show_diff(
+            &rust_label,
+            &sidecar_label,
+            &rust_json,
+            &sidecar_json,
+            &diff_rules,
+            options,
+        );
+        if let Some(ref cassette) = cassette {
+            cassette.finish()?;
+        }
+        return Ok(());
+    }
+
+    // Load the known-differences baseline for this endpoint
+    let baseline_file = match &args.baseline {
+        Some(path) => BaselineFile::load(Path::new(path)).map_err(|e| {
+            eprintln!("Error: {}", e);
+            e
+        })?,
+        None => BaselineFile::default(),
+    };
+    let mut baseline = baseline_file.for_endpoint(&endpoint_type);
+
     let rust_url = &args.url;
     let sidecar_url = &args.sidecar_url;
     let start_block = args.start;
-    let batch_size = args.batch_size;
-    let delay_between_batches = Duration::from_millis(args.delay);
+    let concurrency = args.concurrency.max(1);
 
     // Detect git commits for both repos
     let rust_git = memory::GitInfo::from_repo(&args.rust_repo_path);
@@ -172,25 +523,287 @@ async fn main() -> Result<(), Box<dyn Error>> {
     if let Some(ref git) = sidecar_git {
         println!("Sidecar commit:  {}", git.summary());
     }
+    if let Some(ref path) = args.rules {
+        println!(
+            "Normalization rules: {} ({} of {} rules apply)",
+            path,
+            diff_rules.len(),
+            rules_file.len()
+        );
+    }
+    if args.strict {
+        println!("String comparison: strict (case-sensitive)");
+    }
+    if null_policy != NullPolicy::Strict {
+        println!("Null vs missing: {}", null_policy);
+    }
+    if let Some(error_rules) = diff_rules.error_body_rules() {
+        println!(
+            "Error parity: comparing same-status error bodies ({} error-body rules)",
+            error_rules.len()
+        );
+    }
+    if let Some(ref path) = args.baseline {
+        println!(
+            "Baseline: {} ({} active entries, {} expired)",
+            path,
+            baseline.len(),
+            baseline.expired().len()
+        );
+    }
+    let patch_dir = args.patch_dir.as_deref().map(Path::new);
+    if let Some(dir) = patch_dir {
+        std::fs::create_dir_all(dir)?;
+        println!("JSON Patch output: {}", dir.display());
+    }
+    let spill_dir = args.spill_dir.as_deref().map(Path::new);
+    if let Some(dir) = spill_dir {
+        std::fs::create_dir_all(dir)?;
+        println!("Mismatch responses output: {}", dir.display());
+    }
+    let schema_path = Path::new(&args.schema_file);
+    let use_schema = args.infer_schema || args.validate_schema;
+    let mut schema_store = if use_schema {
+        SchemaFile::load(schema_path)?
+    } else {
+        SchemaFile::default()
+    };
+    let schema_check = if use_schema {
+        let key = SchemaFile::key(&chain, &endpoint_type, &query_params);
+        let pending_dir = spill_dir.map_or_else(std::env::temp_dir, Path::to_path_buf);
+        let check = SchemaCheck::new(
+            &schema_store,
+            key,
+            args.infer_schema,
+            args.validate_schema,
+            &pending_dir,
+        )
+        .map_err(|e| format!("{}: {}", schema_path.display(), e))?;
+        Some(Arc::new(check))
+    } else {
+        None
+    };
+    if let Some(ref check) = schema_check {
+        if args.infer_schema {
+            println!(
+                "Schema inference: {} -> {}",
+                check.key(),
+                schema_path.display()
+            );
+        }
+        if check.is_deferred() {
+            println!(
+                "Schema validation: {} (against the schema inferred in this run, at the end of the scan)",
+                check.key()
+            );
+        } else if let Some(samples) = check.reference_samples() {
+            println!(
+                "Schema validation: {} ({} Sidecar samples)",
+                check.key(),
+                samples
+            );
+        }
+    }
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()?;
+    let custom_headers: Vec<String> = args
+        .custom_headers
+        .as_deref()
+        .map(|list| list.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+    let header_check = (args.compare_headers || !custom_headers.is_empty())
+        .then(|| Arc::new(HeaderCheck::new(&custom_headers)));
+    if let Some(ref check) = header_check {
+        println!("Header comparison: {}", check.names().join(", "));
+    }
+    let block_check = (args.verify_block_hashes && endpoint_type.requires_block())
+        .then(|| Arc::new(BlockHashCheck::new(rust_url, sidecar_url)));
+    if block_check.is_some() {
+        println!("Block hash verification: each block is checked on both servers before comparing");
+    }
 
-    // Determine if we need blocks
-    let end_block = if endpoint_type.requires_block() || endpoint_type.is_range_endpoint() {
-        match args.end {
-            Some(end) => end,
-            None => get_latest_block(&client, rust_url).await?,
+    // Sidecar responses are only cached when pinned to a block; others change over time
+    let sidecar_cache = if args.sidecar_cache {
+        let commit = args
+            .sidecar_commit
+            .clone()
+            .or_else(|| sidecar_git.as_ref().map(|git| git.commit_short.clone()));
+        if !(endpoint_type.requires_block() || endpoint_type.is_range_endpoint()) {
+            println!(
+                "Sidecar cache: not used for {} (responses are not pinned to a block)",
+                endpoint_type
+            );
+            None
+        } else if let Some(commit) = commit {
+            let cache = SidecarCache::open(
+                cache_dir,
+                &commit,
+                &chain,
+                args.sidecar_cache_max_mb.saturating_mul(1024 * 1024),
+            )?;
+            println!("Sidecar cache: {}", cache.scope().display());
+            Some(Arc::new(cache))
+        } else {
+            println!(
+                "Sidecar cache: disabled, the Sidecar commit is unknown (set --sidecar-repo-path or --sidecar-commit)"
+            );
+            None
+        }
+    } else {
+        None
+    };
+
+    let client = match sidecar_cache {
+        Some(ref cache) => new_client()?.with_sidecar_cache(Arc::clone(cache)),
+        None => new_client()?,
+    };
+    if retry_policy.max_retries > 0 {
+        println!(
+            "Retries: up to {} per request (backoff {}ms - {}ms)",
+            retry_policy.max_retries, args.retry_delay_ms, args.retry_max_delay_ms
+        );
+    }
+    if rust_limits.concurrency.is_some() || rust_limits.rate.is_some() {
+        println!("Rust API limits: {}", rust_limits);
+    }
+    if sidecar_limits.concurrency.is_some() || sidecar_limits.rate.is_some() {
+        println!("Sidecar limits: {}", sidecar_limits);
+    }
+    if let Some(ref cassette) = cassette {
+        if cassette.is_replay() {
+            println!(
+                "Replaying: {} recorded responses from {} (no requests are sent)",
+                cassette.len(),
+                cassette.path().display()
+            );
+        } else {
+            println!("Recording responses to: {}", cassette.path().display());
         }
+    }
+
+    // A replay sends no requests, so it needs no waiting for finality: the recording
+    // already holds the re-checked responses
+    let replaying = cassette
+        .as_ref()
+        .is_some_and(|cassette| cassette.is_replay());
+
+    // Caching and --finalized-only rely on the finalized head; other runs can go without
+    let needs_head = if args.finalized_only {
+        Some("--finalized-only")
+    } else if sidecar_cache.is_some() && !replaying {
+        Some("--sidecar-cache")
+    } else {
+        None
+    };
+
+    // Determine if we need blocks
+    let (end_block, finalized_head) =
+        if endpoint_type.requires_block() || endpoint_type.is_range_endpoint() {
+            let heads = get_latest_block(&client, rust_url, sidecar_url, args.max_head_gap).await;
+            match (args.end, heads) {
+                (_, Ok(heads)) if args.finalized_only && heads.finalized.is_none() => {
+                    return Err(
+                        "--finalized-only needs the finalized heads of both servers, \
+                         but Sidecar's could not be read"
+                            .into(),
+                    )
+                }
+                (Some(end), Ok(heads)) if args.finalized_only && end > heads.latest => {
+                    let head = heads.latest;
+                    println!(
+                        "Finalized only: end block capped at #{} (requested #{})",
+                        head, end
+                    );
+                    (head, Some(head))
+                }
+                (Some(end), Ok(heads)) => (end, heads.finalized),
+                (None, Ok(heads)) => (heads.latest, heads.finalized),
+                (Some(end), Err(e)) if needs_head.is_none() => {
+                    eprintln!(
+                        "Warning: could not determine the finalized heads, so no result \
+                         is re-checked once final: {}",
+                        e
+                    );
+                    (end, None)
+                }
+                // Without the heads, blocks that are not final yet would be reported
+                // (and cached) as final
+                (Some(end), Err(e)) => {
+                    return Err(format!(
+                        "Could not determine the finalized heads, so the finality of \
+                         blocks up to #{} is unknown ({} needs it): {}",
+                        end,
+                        needs_head.unwrap_or_default(),
+                        e
+                    )
+                    .into())
+                }
+                (None, Err(e)) => return Err(e),
+            }
+        } else {
+            (0, None) // Not used for non-block endpoints
+        };
+
+    // Without a head finalized on both servers, no response is known to be final
+    let client = if sidecar_cache.is_some() && finalized_head.is_none() && !replaying {
+        eprintln!("Warning: Sidecar cache not used, the finalized heads are unknown");
+        client.without_sidecar_cache()
     } else {
-        0 // Not used for non-block endpoints
+        client
+    };
+
+    // A range response covers many blocks at once and cannot be held back per block,
+    // so a range never reaches above the finalized head
+    let end_block = match finalized_head {
+        Some(head) if endpoint_type.is_range_endpoint() && end_block > head => {
+            if start_block > head {
+                return Err(format!(
+                    "Range {}-{} starts above the finalized head #{}",
+                    start_block, end_block, head
+                )
+                .into());
+            }
+            println!(
+                "Range: end block capped at the finalized head #{} (requested #{})",
+                head, end_block
+            );
+            head
+        }
+        _ => end_block,
     };
 
+    // Results above the finalized head are held back and re-checked once final
+    let finality = finalized_head
+        .filter(|&head| end_block > head && endpoint_type.requires_block() && !replaying)
+        .map(|head| {
+            Arc::new(FinalityGate::new(
+                rust_url,
+                sidecar_url,
+                head,
+                Duration::from_secs(args.finality_timeout),
+            ))
+        });
+    if let Some(ref gate) = finality {
+        println!(
+            "Finality: blocks above #{} are not final yet; results other than a match there are re-checked once final (waiting up to {}s)",
+            gate.finalized(),
+            args.finality_timeout
+        );
+    }
+
+    // Both servers must follow the same chain, or every block compares unrelated data
+    if (endpoint_type.requires_block() || endpoint_type.is_range_endpoint())
+        && verify_block_identities(&client, rust_url, sidecar_url, &[start_block, end_block]).await
+    {
+        println!(
+            "Block identity: #{} and #{} are the same blocks on both servers",
+            start_block, end_block
+        );
+    }
+
     if endpoint_type.requires_block() || endpoint_type.is_range_endpoint() {
         println!("Block range: {} - {}", start_block, end_block);
         if endpoint_type.requires_block() {
-            println!("Batch size: {}", batch_size);
+            println!("Concurrency: {} request pairs in flight", concurrency);
         }
     }
 
@@ -240,7 +853,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let pre_scan_totals = {
         let chain_cov = coverage.get_chain(&chain.to_string(), total_pallets);
         let ep_cov = chain_cov.get_endpoint(&endpoint_type.to_string(), false);
-        (ep_cov.matched, ep_cov.mismatched, ep_cov.rust_errors, ep_cov.sidecar_errors, ep_cov.both_errors)
+        (
+            ep_cov.matched,
+            ep_cov.mismatched,
+            ep_cov.rust_errors,
+            ep_cov.sidecar_errors,
+            ep_cov.both_errors + ep_cov.error_body_mismatches,
+        )
     };
 
     // Route to appropriate scanning function based on endpoint type
@@ -253,9 +872,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
             sidecar_url,
             start_block,
             end_block,
-            batch_size,
-            delay_between_batches,
+            concurrency,
             &query_params,
+            &diff_rules,
+            &mut baseline,
+            patch_dir,
+            spill_dir,
+            schema_check.as_ref(),
+            header_check.as_ref(),
+            block_check.as_ref(),
+            finality.as_ref(),
             &mut coverage,
             total_pallets,
             args.logs,
@@ -271,10 +897,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
             sidecar_url,
             start_block,
             end_block,
-            batch_size,
-            delay_between_batches,
+            concurrency,
             args.pallet.as_deref(),
             &query_params,
+            &diff_rules,
+            &mut baseline,
+            patch_dir,
+            spill_dir,
+            schema_check.as_ref(),
+            header_check.as_ref(),
+            block_check.as_ref(),
+            finality.as_ref(),
             &mut coverage,
             total_pallets,
             args.logs,
@@ -290,10 +923,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
             sidecar_url,
             start_block,
             end_block,
-            batch_size,
-            delay_between_batches,
+            concurrency,
             args.pallet.as_deref(),
             &query_params,
+            &diff_rules,
+            &mut baseline,
+            patch_dir,
+            spill_dir,
+            schema_check.as_ref(),
+            header_check.as_ref(),
+            block_check.as_ref(),
+            finality.as_ref(),
             &mut coverage,
             total_pallets,
             args.logs,
@@ -310,54 +950,158 @@ async fn main() -> Result<(), Box<dyn Error>> {
         println!("  Rust API:    {}", rust_api_url);
         println!("  Sidecar API: {}", sidecar_api_url);
 
-        let (_, result) = http::test_block_compare(
+        let (_, result, stats) = http::test_block_compare(
             client.clone(),
             rust_api_url,
             sidecar_api_url,
             0,
+            Arc::clone(&diff_rules),
+            spill_dir.is_some(),
+            schema_check.clone(),
+            header_check.clone(),
         )
         .await;
 
         let chain_coverage = coverage.get_chain(&chain.to_string(), total_pallets);
         let endpoint_coverage = chain_coverage.get_endpoint(&endpoint_type.to_string(), false);
+        endpoint_coverage.add_retries(stats.retries);
+        if stats.retries > 0 {
+            println!(
+                "\n  Retried {} time(s) after transient failures",
+                stats.retries
+            );
+        }
+        let mut latency = latency::LatencySamples::default();
+        latency.add(stats.rust, stats.sidecar);
+        let latency = latency.summary();
+        endpoint_coverage.set_latency(latency);
+        for line in latency.summary_lines() {
+            println!("{}", line);
+        }
 
         let mut range_issues: Vec<(u64, String)> = Vec::new();
+        let mut range_errors = http::ErrorCounts::default();
         match result {
-            http::TestResult::Match => {
+            http::TestResult::Match { .. } => {
                 println!("\n  Result: MATCH - Both APIs returned identical responses");
                 endpoint_coverage.add_runtime_run(true, None);
             }
-            http::TestResult::Mismatch { diffs, .. } => {
-                println!("\n  Result: MISMATCH - {} difference(s) found", diffs.len());
+            http::TestResult::Mismatch {
+                ref diffs,
+                omitted_diffs: 0,
+                ..
+            } if baseline.covers_all(diffs) => {
+                println!(
+                    "\n  Result: KNOWN MISMATCH - {} baselined difference(s)",
+                    diffs.len()
+                );
+                endpoint_coverage.add_known_run();
+            }
+            http::TestResult::Mismatch {
+                rust_response,
+                sidecar_response,
+                diffs,
+                omitted_diffs,
+            } => {
+                let total = diffs.len() + omitted_diffs;
+                println!("\n  Result: MISMATCH - {} difference(s) found", total);
                 for (i, diff) in diffs.iter().take(5).enumerate() {
                     println!("    {}. {}", i + 1, diff);
                 }
-                if diffs.len() > 5 {
-                    println!("    ... and {} more", diffs.len() - 5);
+                if total > 5 {
+                    println!("    ... and {} more", total - 5);
                 }
                 endpoint_coverage.add_runtime_run(false, None);
                 let diff_summary = diffs.iter().take(10).map(|d| d.to_string()).collect::<Vec<_>>().join("; ");
-                range_issues.push((0, format!("MISMATCH ({} diffs): {}", diffs.len(), diff_summary)));
+                range_issues.push((0, format!("MISMATCH ({} diffs): {}", total, diff_summary)));
+                if let (Some(dir), Some(rust), Some(sidecar)) =
+                    (spill_dir, &rust_response, &sidecar_response)
+                {
+                    let (rust_path, sidecar_path) = spill_responses(
+                        dir,
+                        &format!("{}_{}", chain, endpoint_type),
+                        rust,
+                        sidecar,
+                    )?;
+                    println!(
+                        "  Responses saved to: {} + {}",
+                        rust_path.display(),
+                        sidecar_path.display()
+                    );
+                }
+                if let Some(dir) = patch_dir {
+                    let name = format!("{}_{}", chain, endpoint_type);
+                    match scanner::write_patch(dir, &name, &diffs, omitted_diffs)? {
+                        Some(path) => println!("  JSON Patch saved to: {}", path.display()),
+                        None => println!("  Warning: {}", scanner::patch_skipped(omitted_diffs)),
+                    }
+                }
             }
             http::TestResult::RustError(e) => {
                 println!("\n  Rust API error: {}", e);
-                endpoint_coverage.add_runtime_run(false, Some("rust_error"));
-                range_issues.push((0, format!("RUST API ERROR: {}", e)));
+                endpoint_coverage.add_runtime_run(false, Some(&e.category()));
+                range_errors.add("rust", &e);
+                range_issues.push((0, format!("RUST API ERROR ({}): {}", e.category(), e)));
             }
             http::TestResult::SidecarError(e) => {
                 println!("\n  Sidecar error: {}", e);
-                endpoint_coverage.add_runtime_run(false, Some("sidecar_error"));
-                range_issues.push((0, format!("SIDECAR ERROR: {}", e)));
+                endpoint_coverage.add_runtime_run(false, Some(&e.category()));
+                range_errors.add("sidecar", &e);
+                range_issues.push((0, format!("SIDECAR ERROR ({}): {}", e.category(), e)));
             }
             http::TestResult::BothError { rust_error, sidecar_error } => {
                 println!("\n  Both APIs errored:");
                 println!("    Rust: {}", rust_error);
                 println!("    Sidecar: {}", sidecar_error);
-                endpoint_coverage.add_runtime_run(false, Some("both_error"));
-                if rust_error != sidecar_error {
+                endpoint_coverage.add_runtime_run(false, Some(&rust_error.category()));
+                if !rust_error.same_as(&sidecar_error) {
+                    range_errors.add("rust", &rust_error);
+                    range_errors.add("sidecar", &sidecar_error);
                     range_issues.push((0, format!("BOTH ERRORS (diff codes) - Rust: {}, Sidecar: {}", rust_error, sidecar_error)));
                 }
             }
+            http::TestResult::ErrorBodyMismatch {
+                rust_error,
+                sidecar_error,
+                diffs,
+                omitted_diffs,
+            } => {
+                let total = diffs.len() + omitted_diffs;
+                println!(
+                    "\n  Result: ERROR BODY MISMATCH - {} difference(s) between the {} bodies",
+                    total,
+                    rust_error.category()
+                );
+                for (i, diff) in diffs.iter().take(5).enumerate() {
+                    println!("    {}. {}", i + 1, diff);
+                }
+                println!("    Rust: {}", rust_error);
+                println!("    Sidecar: {}", sidecar_error);
+                endpoint_coverage.add_error_body_run();
+                let diff_summary = diffs
+                    .iter()
+                    .take(10)
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                range_issues.push((
+                    0,
+                    format!(
+                        "ERROR BODY MISMATCH ({}, {} diffs): {}",
+                        rust_error.category(),
+                        total,
+                        diff_summary
+                    ),
+                ));
+            }
+            http::TestResult::Desync { .. } => {
+                unreachable!("block identities are not verified for range endpoints")
+            }
+        }
+        endpoint_coverage.add_error_counts(&range_errors);
+        endpoint_coverage.set_baseline_stale(baseline.stale().len());
+        for entry in baseline.stale() {
+            println!("  Stale baseline entry: {}", entry);
         }
         range_issues
     } else {
@@ -368,6 +1112,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
             rust_url,
             sidecar_url,
             &query_params,
+            &diff_rules,
+            &mut baseline,
+            patch_dir,
+            spill_dir,
+            schema_check.as_ref(),
+            header_check.as_ref(),
             &mut coverage,
             total_pallets,
             args.logs,
@@ -378,7 +1128,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Write query params coverage if --query-params was used
     if !query_params.is_empty() {
         // Compute delta from this run only (coverage is cumulative)
-        let (qp_matched, qp_mismatched, qp_rust_errors, qp_sidecar_errors, qp_both_errors) = {
+        let (
+            qp_matched,
+            qp_mismatched,
+            qp_rust_errors,
+            qp_sidecar_errors,
+            qp_both_errors,
+            qp_latency,
+        ) = {
             let chain_cov = coverage.get_chain(&chain.to_string(), total_pallets);
             let ep_cov = chain_cov.get_endpoint(&endpoint_type.to_string(), false);
             (
@@ -386,7 +1143,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 ep_cov.mismatched - pre_scan_totals.1,
                 ep_cov.rust_errors - pre_scan_totals.2,
                 ep_cov.sidecar_errors - pre_scan_totals.3,
-                ep_cov.both_errors - pre_scan_totals.4,
+                // Error body mismatches count as both-error results here
+                ep_cov.both_errors + ep_cov.error_body_mismatches - pre_scan_totals.4,
+                ep_cov.latency,
             )
         };
 
@@ -406,6 +1165,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             rust_errors: qp_rust_errors,
             sidecar_errors: qp_sidecar_errors,
             both_errors: qp_both_errors,
+            latency: qp_latency,
             issues: scan_issues,
         });
 
@@ -423,6 +1183,49 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    // Report schema violations and persist the inferred schema
+    if let Some(ref check) = schema_check {
+        if check.is_validating() {
+            check.validate_pending(&diff_rules);
+            check.print_summary();
+            let schema_report = format!("reports/schema_{}_{}.md", chain, endpoint_type);
+            std::fs::create_dir_all("reports")?;
+            std::fs::write(&schema_report, check.to_markdown())?;
+            println!("Schema validation report saved to: {}", schema_report);
+        }
+        if args.infer_schema {
+            check.store_learned(&mut schema_store);
+            schema_store.save(schema_path)?;
+            println!("Inferred schema saved to: {}", schema_path.display());
+        }
+    }
+
+    // Report header mismatches
+    if let Some(ref check) = header_check {
+        check.print_summary();
+        let header_report = format!("reports/headers_{}_{}.md", chain, endpoint_type);
+        std::fs::create_dir_all("reports")?;
+        std::fs::write(&header_report, check.to_markdown(&chain, &endpoint_type))?;
+        println!("Header mismatch report saved to: {}", header_report);
+    }
+
+    if let Some(ref check) = block_check {
+        println!("\nBlock hash verification: {}", check.summary());
+    }
+    if let Some(ref gate) = finality {
+        println!("\nFinality re-checks: {}", gate.summary());
+    }
+
+    // Complete the cassette
+    if let Some(ref cassette) = cassette {
+        cassette.finish()?;
+        println!("\nCassette: {}", cassette.summary());
+    }
+    if let Some(ref cache) = sidecar_cache {
+        cache.prune();
+        println!("Sidecar cache: {}", cache.summary());
+    }
+
     // Stop memory monitoring and print report
     if let Some(monitor) = memory_monitor {
         let mut memory_report = monitor.stop().await;
@@ -261,7 +261,7 @@ impl MemoryMonitor {
     /// Stop monitoring and return the memory report.
     pub async fn stop(self) -> MemoryReport {
         let _ = self.shutdown.send(true);
-        self.handle.await.unwrap_or_else(|_| MemoryReport {
+        self.handle.await.unwrap_or(MemoryReport {
             rust_api: None,
             sidecar: None,
             duration_secs: 0.0,
@@ -374,23 +374,21 @@ impl MemoryReport {
         md.push_str("| Server | PID | Baseline | Peak | Average | Final | Growth |\n");
         md.push_str("|--------|-----|----------|------|---------|-------|--------|\n");
 
-        for stats_opt in [&self.rust_api, &self.sidecar] {
-            if let Some(stats) = stats_opt {
-                let sign = if stats.growth_kb >= 0 { "+" } else { "" };
-                md.push_str(&format!(
-                    "| {} | {} | {:.1} MB | {:.1} MB | {:.1} MB | {:.1} MB | {}{:.1} MB ({}{:.1}%) |\n",
-                    stats.label,
-                    stats.pid,
-                    stats.baseline_kb as f64 / 1024.0,
-                    stats.peak_kb as f64 / 1024.0,
-                    stats.average_kb as f64 / 1024.0,
-                    stats.final_kb as f64 / 1024.0,
-                    sign,
-                    stats.growth_kb as f64 / 1024.0,
-                    sign,
-                    stats.growth_percent,
-                ));
-            }
+        for stats in [&self.rust_api, &self.sidecar].into_iter().flatten() {
+            let sign = if stats.growth_kb >= 0 { "+" } else { "" };
+            md.push_str(&format!(
+                "| {} | {} | {:.1} MB | {:.1} MB | {:.1} MB | {:.1} MB | {}{:.1} MB ({}{:.1}%) |\n",
+                stats.label,
+                stats.pid,
+                stats.baseline_kb as f64 / 1024.0,
+                stats.peak_kb as f64 / 1024.0,
+                stats.average_kb as f64 / 1024.0,
+                stats.final_kb as f64 / 1024.0,
+                sign,
+                stats.growth_kb as f64 / 1024.0,
+                sign,
+                stats.growth_percent,
+            ));
         }
 
         md.push('\n');
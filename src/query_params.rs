@@ -324,6 +324,7 @@ impl QueryParam {
     }
 
     /// Check if this param is supported by the given endpoint.
+    #[allow(dead_code)]
     pub fn is_supported_by(&self, endpoint: &EndpointType) -> bool {
         supported_query_params(endpoint).contains(&self.kind())
     }
@@ -390,6 +391,8 @@ pub fn parse_query_params(
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::latency::LatencyComparison;
+
 /// Merge overlapping or adjacent block ranges in-place.
 fn merge_ranges(ranges: &mut Vec<(u32, u32)>) {
     if ranges.is_empty() {
@@ -442,6 +445,9 @@ pub struct QpEndpointCoverage {
     pub rust_errors: u32,
     pub sidecar_errors: u32,
     pub both_errors: u32,
+    /// Rust vs Sidecar latency in the latest run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyComparison>,
     pub last_tested: String,
     /// Individual issues: (block_id, description)
     #[serde(default)]
@@ -521,7 +527,7 @@ impl QpCoverageData {
         let ep_map = chain_cov
             .endpoints
             .entry(info.endpoint_name.to_string())
-            .or_insert_with(HashMap::new);
+            .or_default();
 
         let cov = ep_map.entry(param_key.clone()).or_insert_with(|| {
             QpEndpointCoverage {
@@ -532,6 +538,7 @@ impl QpCoverageData {
                 rust_errors: 0,
                 sidecar_errors: 0,
                 both_errors: 0,
+                latency: None,
                 last_tested: String::new(),
                 issues: Vec::new(),
             }
@@ -546,6 +553,9 @@ impl QpCoverageData {
         cov.rust_errors += info.rust_errors;
         cov.sidecar_errors += info.sidecar_errors;
         cov.both_errors += info.both_errors;
+        if info.latency.is_some() {
+            cov.latency = info.latency;
+        }
         cov.issues.extend_from_slice(&info.issues);
         cov.last_tested = chrono::Utc::now().to_rfc3339();
     }
@@ -706,6 +716,7 @@ pub struct QpRunInfo<'a> {
     pub rust_errors: u32,
     pub sidecar_errors: u32,
     pub both_errors: u32,
+    pub latency: Option<LatencyComparison>,
     pub issues: Vec<(u64, String)>,
 }
 
@@ -2,17 +2,26 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+use crate::baseline::Baseline;
 use crate::chains::Chain;
+use crate::cluster::{DiffCluster, DiffClusters};
 use crate::endpoints::EndpointType;
+use crate::http::ErrorCounts;
+use crate::latency::LatencyComparison;
 
 pub struct PalletResult {
     pub name: String,
     pub index: u8,
     pub matched: u32,
     pub mismatched: u32,
+    pub known: u32,
     pub rust_errors: u32,
     pub sidecar_errors: u32,
     pub both_errors: u32,
+    pub error_body_mismatches: u32,
+    pub desyncs: u32,
+    /// Rust vs Sidecar latency over this pallet / account
+    pub latency: LatencyComparison,
     pub issues: Vec<(u64, String)>,
 }
 
@@ -21,18 +30,247 @@ pub struct AccountResult {
     pub address: String,
     pub matched: u32,
     pub mismatched: u32,
+    pub known: u32,
     pub rust_errors: u32,
     pub sidecar_errors: u32,
     pub both_errors: u32,
+    pub error_body_mismatches: u32,
+    pub desyncs: u32,
+    /// Rust vs Sidecar latency over this pallet / account
+    pub latency: LatencyComparison,
     pub issues: Vec<(u64, String)>,
 }
 
+/// Summary lines describing baseline suppression (stale and expired entries)
+fn baseline_summary_lines(baseline: &Baseline) -> Vec<String> {
+    let mut lines = Vec::new();
+    let stale = baseline.stale();
+    if !stale.is_empty() {
+        lines.push(format!(
+            "Stale baseline entries (baselined but not seen): {}",
+            stale.len()
+        ));
+        for entry in stale {
+            lines.push(format!("  - {}", entry));
+        }
+    }
+    if !baseline.expired().is_empty() {
+        lines.push(format!(
+            "Expired baseline entries (no longer suppressing): {}",
+            baseline.expired().len()
+        ));
+        for entry in baseline.expired() {
+            lines.push(format!("  - {}", entry));
+        }
+    }
+    lines
+}
+
+/// Markdown section describing baseline suppression (stale and expired entries)
+fn write_baseline_markdown(f: &mut File, baseline: &Baseline) {
+    let stale = baseline.stale();
+    if !stale.is_empty() {
+        writeln!(f).ok();
+        writeln!(f, "## Stale Baseline Entries").ok();
+        writeln!(f).ok();
+        for entry in stale {
+            writeln!(f, "- `{}`", entry).ok();
+        }
+    }
+    if !baseline.expired().is_empty() {
+        writeln!(f).ok();
+        writeln!(f, "## Expired Baseline Entries").ok();
+        writeln!(f).ok();
+        for entry in baseline.expired() {
+            writeln!(f, "- `{}`", entry).ok();
+        }
+    }
+}
+
+/// Markdown table of errors by side, kind and status code
+fn write_errors_markdown(f: &mut File, errors: &ErrorCounts) {
+    if errors.is_empty() {
+        return;
+    }
+    writeln!(f).ok();
+    writeln!(f, "## Errors by Kind").ok();
+    writeln!(f).ok();
+    writeln!(f, "| API | Kind | Status | Count |").ok();
+    writeln!(f, "|-----|------|--------|-------|").ok();
+    for (side, kind, status, count) in errors.iter() {
+        let status = status.map_or("-".to_string(), |s| s.to_string());
+        writeln!(f, "| {} | {} | {} | {} |", side, kind, status, count).ok();
+    }
+}
+
+/// Maximum number of clusters listed in console summaries
+const MAX_SUMMARY_CLUSTERS: usize = 20;
+
+/// Console lines for one list of clusters
+fn cluster_lines(title: &str, clusters: &[&DiffCluster]) -> Vec<String> {
+    let mut lines = Vec::new();
+    if clusters.is_empty() {
+        return lines;
+    }
+
+    lines.push(format!("\n{}", "=".repeat(90)));
+    lines.push(format!("{:^90}", title).trim_end().to_string());
+    lines.push("=".repeat(90));
+    for (i, cluster) in clusters.iter().enumerate() {
+        if i >= MAX_SUMMARY_CLUSTERS {
+            lines.push(format!(
+                "  ... and {} more clusters",
+                clusters.len() - MAX_SUMMARY_CLUSTERS
+            ));
+            break;
+        }
+        lines.push(format!(
+            "  {}. {} - {} response(s), {} diff(s), blocks {}-{}",
+            i + 1,
+            cluster,
+            cluster.occurrences,
+            cluster.key.diff_count,
+            cluster.first_block,
+            cluster.last_block
+        ));
+        if let Some(url) = cluster.example_urls.first() {
+            lines.push(format!("     e.g. {}", url));
+        }
+    }
+    lines
+}
+
+/// Per-scope p50 / p99 latency lines (one per pallet or account with samples)
+fn scope_latency_lines<'a>(
+    scope: &str,
+    rows: impl Iterator<Item = (&'a str, &'a LatencyComparison)>,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (label, latency) in rows.filter(|(_, l)| !l.is_empty()) {
+        if lines.is_empty() {
+            lines.push(format!(
+                "  {:<25} {:>12} {:>12} {:>12} {:>12}",
+                scope, "Rust p50", "Rust p99", "Sidecar p50", "Sidecar p99"
+            ));
+        }
+        lines.push(format!(
+            "  {:<25} {:>10.1}ms {:>10.1}ms {:>10.1}ms {:>10.1}ms",
+            label,
+            latency.rust.p50_ms,
+            latency.rust.p99_ms,
+            latency.sidecar.p50_ms,
+            latency.sidecar.p99_ms
+        ));
+    }
+    lines
+}
+
+/// Summary lines listing diff clusters (most frequent first), followed by the
+/// values that were only equal after a normalization
+fn cluster_summary_lines(clusters: &DiffClusters) -> Vec<String> {
+    let mut lines = Vec::new();
+    let totals = clusters.totals_by_type();
+    if !totals.is_empty() {
+        lines.push(format!(
+            "Diffs by type: {}",
+            totals
+                .iter()
+                .map(|(diff_type, count)| format!("{:?} {}", diff_type, count))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    lines.extend(cluster_lines("DIFF CLUSTERS", &clusters.sorted()));
+    lines.extend(cluster_lines(
+        "EQUIVALENT VALUES (equal after normalization)",
+        &clusters.sorted_equivalent(),
+    ));
+    lines
+}
+
+/// Markdown table for one list of clusters
+fn write_cluster_table(f: &mut File, title: &str, clusters: &[&DiffCluster]) {
+    if clusters.is_empty() {
+        return;
+    }
+
+    writeln!(f).ok();
+    writeln!(f, "## {}", title).ok();
+    writeln!(f).ok();
+    writeln!(
+        f,
+        "| # | Path | Diff Type | Values (Rust vs Sidecar) | Responses | Diffs | First Block | Last Block | Examples |"
+    )
+    .ok();
+    writeln!(
+        f,
+        "|---|------|-----------|--------------------------|-----------|-------|-------------|------------|----------|"
+    )
+    .ok();
+    for (i, cluster) in clusters.iter().enumerate() {
+        let examples = cluster
+            .example_urls
+            .iter()
+            .enumerate()
+            .map(|(n, url)| format!("[{}]({})", n + 1, url))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut diff_type = match cluster.key.normalization {
+            Some(normalization) => format!("{:?} ({})", cluster.key.diff_type, normalization),
+            None => format!("{:?}", cluster.key.diff_type),
+        };
+        if let Some(delta) = cluster.key.max_delta {
+            diff_type.push_str(&format!(", max {}", delta));
+        }
+        writeln!(
+            f,
+            "| {} | `{}` | {} | {} vs {} | {} | {} | {} | {} | {} |",
+            i + 1,
+            cluster.key.path.replace('|', "\\|"),
+            diff_type,
+            cluster.key.rust_type,
+            cluster.key.sidecar_type,
+            cluster.occurrences,
+            cluster.key.diff_count,
+            cluster.first_block,
+            cluster.last_block,
+            examples
+        )
+        .ok();
+    }
+}
+
+/// Markdown tables of diff clusters and equivalent values, most frequent first
+fn write_clusters_markdown(f: &mut File, clusters: &DiffClusters) {
+    let totals = clusters.totals_by_type();
+    if !totals.is_empty() {
+        writeln!(f).ok();
+        writeln!(f, "## Diffs by Type").ok();
+        writeln!(f).ok();
+        writeln!(f, "| Diff Type | Count |").ok();
+        writeln!(f, "|-----------|-------|").ok();
+        for (diff_type, count) in totals {
+            writeln!(f, "| {:?} | {} |", diff_type, count).ok();
+        }
+    }
+    write_cluster_table(f, "Diff Clusters", &clusters.sorted());
+    write_cluster_table(
+        f,
+        "Equivalent Values (equal after normalization)",
+        &clusters.sorted_equivalent(),
+    );
+}
+
 pub fn print_pallet_summary(
     results: &[PalletResult],
     chain: &Chain,
     endpoint_type: &EndpointType,
     start_block: u32,
     end_block: u32,
+    baseline: &Baseline,
+    clusters: &DiffClusters,
+    errors: &ErrorCounts,
+    latency: &LatencyComparison,
     create_logs: bool,
 ) {
     // Create summary log file (only if --logs flag is set)
@@ -65,29 +303,38 @@ pub fn print_pallet_summary(
     log_line!("Total pallets scanned: {}\n", results.len());
 
     log_line!(
-        "{:<25} {:>8} {:>10} {:>10} {:>10} {:>16} {:>8}",
+        "{:<25} {:>8} {:>10} {:>8} {:>10} {:>10} {:>16} {:>10} {:>8} {:>8}",
         "Pallet",
         "Matched",
         "Mismatch",
+        "Known",
         "RustErr",
         "SidecarErr",
         "BothErr(diff)",
+        "BodyDiff",
+        "Desync",
         "Rate"
     );
-    log_line!("{}", "-".repeat(96));
+    log_line!("{}", "-".repeat(125));
 
     let mut total_matched = 0u32;
     let mut total_mismatched = 0u32;
+    let mut total_known = 0u32;
     let mut total_rust_errors = 0u32;
     let mut total_sidecar_errors = 0u32;
     let mut total_both_errors = 0u32;
+    let mut total_error_body_mismatches = 0u32;
+    let mut total_desyncs = 0u32;
 
     for result in results {
         let total = result.matched
             + result.mismatched
+            + result.known
             + result.rust_errors
             + result.sidecar_errors
-            + result.both_errors;
+            + result.both_errors
+            + result.error_body_mismatches
+            + result.desyncs;
         let rate = if total > 0 {
             (result.matched as f64 / total as f64) * 100.0
         } else {
@@ -95,45 +342,76 @@ pub fn print_pallet_summary(
         };
 
         log_line!(
-            "{:<25} {:>8} {:>10} {:>10} {:>10} {:>16} {:>7.2}%",
+            "{:<25} {:>8} {:>10} {:>8} {:>10} {:>10} {:>16} {:>10} {:>8} {:>7.2}%",
             result.name,
             result.matched,
             result.mismatched,
+            result.known,
             result.rust_errors,
             result.sidecar_errors,
             result.both_errors,
+            result.error_body_mismatches,
+            result.desyncs,
             rate
         );
 
         total_matched += result.matched;
         total_mismatched += result.mismatched;
+        total_known += result.known;
         total_rust_errors += result.rust_errors;
         total_sidecar_errors += result.sidecar_errors;
         total_both_errors += result.both_errors;
+        total_error_body_mismatches += result.error_body_mismatches;
+        total_desyncs += result.desyncs;
     }
 
-    log_line!("{}", "-".repeat(96));
+    log_line!("{}", "-".repeat(125));
     let overall_total = total_matched
         + total_mismatched
+        + total_known
         + total_rust_errors
         + total_sidecar_errors
-        + total_both_errors;
+        + total_both_errors
+        + total_error_body_mismatches
+        + total_desyncs;
     let overall_rate = if overall_total > 0 {
         (total_matched as f64 / overall_total as f64) * 100.0
     } else {
         0.0
     };
     log_line!(
-        "{:<25} {:>8} {:>10} {:>10} {:>10} {:>16} {:>7.2}%",
+        "{:<25} {:>8} {:>10} {:>8} {:>10} {:>10} {:>16} {:>10} {:>8} {:>7.2}%",
         "TOTAL",
         total_matched,
         total_mismatched,
+        total_known,
         total_rust_errors,
         total_sidecar_errors,
         total_both_errors,
+        total_error_body_mismatches,
+        total_desyncs,
         overall_rate
     );
 
+    for line in baseline_summary_lines(baseline) {
+        log_line!("{}", line);
+    }
+    for line in cluster_summary_lines(clusters) {
+        log_line!("{}", line);
+    }
+    if !errors.is_empty() {
+        log_line!("Errors by kind: {}", errors.summary());
+    }
+    for line in latency.summary_lines() {
+        log_line!("{}", line);
+    }
+    for line in scope_latency_lines(
+        "Pallet",
+        results.iter().map(|r| (r.name.as_str(), &r.latency)),
+    ) {
+        log_line!("{}", line);
+    }
+
     // Print issue summary
     let pallets_with_issues: Vec<_> = results.iter().filter(|r| !r.issues.is_empty()).collect();
 
@@ -169,10 +447,17 @@ pub fn print_block_summary(
     end_block: u32,
     matched: u32,
     mismatched: u32,
+    known: u32,
     rust_errors: u32,
     sidecar_errors: u32,
     both_errors: u32,
+    error_body_mismatches: u32,
+    desyncs: u32,
     issues: &[(u64, String)],
+    baseline: &Baseline,
+    clusters: &DiffClusters,
+    errors: &ErrorCounts,
+    latency: &LatencyComparison,
     create_logs: bool,
 ) {
     // Create summary log file (only if --logs flag is set)
@@ -203,7 +488,14 @@ pub fn print_block_summary(
     log_line!("Endpoint: {}", endpoint_type);
     log_line!("Block range: {} - {}\n", start_block, end_block);
 
-    let total = matched + mismatched + rust_errors + sidecar_errors + both_errors;
+    let total = matched
+        + mismatched
+        + known
+        + rust_errors
+        + sidecar_errors
+        + both_errors
+        + error_body_mismatches
+        + desyncs;
     let rate = if total > 0 {
         (matched as f64 / total as f64) * 100.0
     } else {
@@ -212,9 +504,31 @@ pub fn print_block_summary(
 
     log_line!("Matched:        {} / {} ({:.2}%)", matched, total, rate);
     log_line!("Mismatched:     {}", mismatched);
+    log_line!("Known:          {}", known);
     log_line!("Rust Errors:    {}", rust_errors);
     log_line!("Sidecar Errors: {}", sidecar_errors);
     log_line!("Both Errors (diff codes): {}", both_errors);
+    if error_body_mismatches > 0 {
+        log_line!(
+            "Error Body Mismatches (same status): {}",
+            error_body_mismatches
+        );
+    }
+    if desyncs > 0 {
+        log_line!("Fork/Desync (blocks differ): {}", desyncs);
+    }
+    for line in baseline_summary_lines(baseline) {
+        log_line!("{}", line);
+    }
+    for line in cluster_summary_lines(clusters) {
+        log_line!("{}", line);
+    }
+    if !errors.is_empty() {
+        log_line!("Errors by kind: {}", errors.summary());
+    }
+    for line in latency.summary_lines() {
+        log_line!("{}", line);
+    }
 
     if !issues.is_empty() {
         log_line!("\n{}", "=".repeat(90));
@@ -244,6 +558,10 @@ pub fn print_account_summary(
     endpoint_type: &EndpointType,
     start_block: u32,
     end_block: u32,
+    baseline: &Baseline,
+    clusters: &DiffClusters,
+    errors: &ErrorCounts,
+    latency: &LatencyComparison,
     create_logs: bool,
 ) {
     // Create summary log file (only if --logs flag is set)
@@ -276,29 +594,38 @@ pub fn print_account_summary(
     log_line!("Total accounts scanned: {}\n", results.len());
 
     log_line!(
-        "{:<15} {:>8} {:>10} {:>10} {:>10} {:>16} {:>8}",
+        "{:<15} {:>8} {:>10} {:>8} {:>10} {:>10} {:>16} {:>10} {:>8} {:>8}",
         "Account",
         "Matched",
         "Mismatch",
+        "Known",
         "RustErr",
         "SidecarErr",
         "BothErr(diff)",
+        "BodyDiff",
+        "Desync",
         "Rate"
     );
-    log_line!("{}", "-".repeat(96));
+    log_line!("{}", "-".repeat(125));
 
     let mut total_matched = 0u32;
     let mut total_mismatched = 0u32;
+    let mut total_known = 0u32;
     let mut total_rust_errors = 0u32;
     let mut total_sidecar_errors = 0u32;
     let mut total_both_errors = 0u32;
+    let mut total_error_body_mismatches = 0u32;
+    let mut total_desyncs = 0u32;
 
     for result in results {
         let total = result.matched
             + result.mismatched
+            + result.known
             + result.rust_errors
             + result.sidecar_errors
-            + result.both_errors;
+            + result.both_errors
+            + result.error_body_mismatches
+            + result.desyncs;
         let rate = if total > 0 {
             (result.matched as f64 / total as f64) * 100.0
         } else {
@@ -306,45 +633,76 @@ pub fn print_account_summary(
         };
 
         log_line!(
-            "{:<15} {:>8} {:>10} {:>10} {:>10} {:>16} {:>7.2}%",
+            "{:<15} {:>8} {:>10} {:>8} {:>10} {:>10} {:>16} {:>10} {:>8} {:>7.2}%",
             result.label,
             result.matched,
             result.mismatched,
+            result.known,
             result.rust_errors,
             result.sidecar_errors,
             result.both_errors,
+            result.error_body_mismatches,
+            result.desyncs,
             rate
         );
 
         total_matched += result.matched;
         total_mismatched += result.mismatched;
+        total_known += result.known;
         total_rust_errors += result.rust_errors;
         total_sidecar_errors += result.sidecar_errors;
         total_both_errors += result.both_errors;
+        total_error_body_mismatches += result.error_body_mismatches;
+        total_desyncs += result.desyncs;
     }
 
-    log_line!("{}", "-".repeat(96));
+    log_line!("{}", "-".repeat(125));
     let overall_total = total_matched
         + total_mismatched
+        + total_known
         + total_rust_errors
         + total_sidecar_errors
-        + total_both_errors;
+        + total_both_errors
+        + total_error_body_mismatches
+        + total_desyncs;
     let overall_rate = if overall_total > 0 {
         (total_matched as f64 / overall_total as f64) * 100.0
     } else {
         0.0
     };
     log_line!(
-        "{:<15} {:>8} {:>10} {:>10} {:>10} {:>16} {:>7.2}%",
+        "{:<15} {:>8} {:>10} {:>8} {:>10} {:>10} {:>16} {:>10} {:>8} {:>7.2}%",
         "TOTAL",
         total_matched,
         total_mismatched,
+        total_known,
         total_rust_errors,
         total_sidecar_errors,
         total_both_errors,
+        total_error_body_mismatches,
+        total_desyncs,
         overall_rate
     );
 
+    for line in baseline_summary_lines(baseline) {
+        log_line!("{}", line);
+    }
+    for line in cluster_summary_lines(clusters) {
+        log_line!("{}", line);
+    }
+    if !errors.is_empty() {
+        log_line!("Errors by kind: {}", errors.summary());
+    }
+    for line in latency.summary_lines() {
+        log_line!("{}", line);
+    }
+    for line in scope_latency_lines(
+        "Account",
+        results.iter().map(|r| (r.label.as_str(), &r.latency)),
+    ) {
+        log_line!("{}", line);
+    }
+
     // Print issue summary
     let accounts_with_issues: Vec<_> = results.iter().filter(|r| !r.issues.is_empty()).collect();
 
@@ -384,11 +742,19 @@ pub fn write_pallet_mismatch_report(
     end_block: u32,
     rust_url: &str,
     sidecar_url: &str,
+    baseline: &Baseline,
+    clusters: &DiffClusters,
+    errors: &ErrorCounts,
 ) {
     let pallets_with_issues: Vec<_> = results
         .iter()
         .filter(|r| {
-            r.mismatched > 0 || r.rust_errors > 0 || r.sidecar_errors > 0 || r.both_errors > 0
+            r.mismatched > 0
+                || r.rust_errors > 0
+                || r.sidecar_errors > 0
+                || r.both_errors > 0
+                || r.error_body_mismatches > 0
+                || r.desyncs > 0
         })
         .collect();
 
@@ -420,20 +786,23 @@ pub fn write_pallet_mismatch_report(
 
         writeln!(
             f,
-            "| Pallet | Matched | Mismatch | Rust Err | Sidecar Err | Both Err (diff codes) | Rate |"
+            "| Pallet | Matched | Mismatch (new) | Known | Rust Err | Sidecar Err | Both Err (diff codes) | Error Body Diff | Fork/Desync | Rate |"
         ).ok();
         writeln!(
             f,
-            "|--------|---------|----------|----------|-------------|----------------------|------|"
+            "|--------|---------|----------------|-------|----------|-------------|----------------------|-----------------|-------------|------|"
         )
         .ok();
 
         for result in results {
             let total = result.matched
                 + result.mismatched
+                + result.known
                 + result.rust_errors
                 + result.sidecar_errors
-                + result.both_errors;
+                + result.both_errors
+                + result.error_body_mismatches
+                + result.desyncs;
             let rate = if total > 0 {
                 (result.matched as f64 / total as f64) * 100.0
             } else {
@@ -442,7 +811,9 @@ pub fn write_pallet_mismatch_report(
             let has_issues = result.mismatched > 0
                 || result.rust_errors > 0
                 || result.sidecar_errors > 0
-                || result.both_errors > 0;
+                || result.both_errors > 0
+                || result.error_body_mismatches > 0
+                || result.desyncs > 0;
             let name = if has_issues {
                 format!("**{}**", result.name)
             } else {
@@ -450,18 +821,25 @@ pub fn write_pallet_mismatch_report(
             };
             writeln!(
                 f,
-                "| {} | {} | {} | {} | {} | {} | {:.1}% |",
+                "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {:.1}% |",
                 name,
                 result.matched,
                 result.mismatched,
+                result.known,
                 result.rust_errors,
                 result.sidecar_errors,
                 result.both_errors,
+                result.error_body_mismatches,
+                result.desyncs,
                 rate
             )
             .ok();
         }
 
+        write_clusters_markdown(&mut f, clusters);
+        write_errors_markdown(&mut f, errors);
+        write_baseline_markdown(&mut f, baseline);
+
         println!("Summary report saved to: {}", summary_filename);
     } else {
         eprintln!("Failed to create summary file: {}", summary_filename);
@@ -480,6 +858,11 @@ pub fn write_pallet_mismatch_report(
         .ok();
         writeln!(f).ok();
 
+        write_clusters_markdown(&mut f, clusters);
+        if !clusters.is_empty() {
+            writeln!(f).ok();
+        }
+
         for result in &pallets_with_issues {
             writeln!(f, "## {} (index {})", result.name, result.index).ok();
             writeln!(f).ok();
@@ -515,10 +898,16 @@ pub fn write_block_mismatch_report(
     sidecar_url: &str,
     matched: u32,
     mismatched: u32,
+    known: u32,
     rust_errors: u32,
     sidecar_errors: u32,
     both_errors: u32,
+    error_body_mismatches: u32,
+    desyncs: u32,
     issues: &[(u64, String)],
+    baseline: &Baseline,
+    clusters: &DiffClusters,
+    errors: &ErrorCounts,
 ) {
     if issues.is_empty() {
         return;
@@ -531,7 +920,14 @@ pub fn write_block_mismatch_report(
     let summary_filename = format!("{}.md", base);
     let details_filename = format!("{}_details.md", base);
 
-    let total = matched + mismatched + rust_errors + sidecar_errors + both_errors;
+    let total = matched
+        + mismatched
+        + known
+        + rust_errors
+        + sidecar_errors
+        + both_errors
+        + error_body_mismatches
+        + desyncs;
     let rate = if total > 0 {
         (matched as f64 / total as f64) * 100.0
     } else {
@@ -556,10 +952,23 @@ pub fn write_block_mismatch_report(
         writeln!(f, "| Metric | Count |").ok();
         writeln!(f, "|--------|-------|").ok();
         writeln!(f, "| Matched | {} / {} ({:.1}%) |", matched, total, rate).ok();
-        writeln!(f, "| Mismatched | {} |", mismatched).ok();
+        writeln!(f, "| Mismatched (new) | {} |", mismatched).ok();
+        writeln!(f, "| Known (baselined) | {} |", known).ok();
+        writeln!(f, "| Stale baseline entries | {} |", baseline.stale().len()).ok();
         writeln!(f, "| Rust Errors | {} |", rust_errors).ok();
         writeln!(f, "| Sidecar Errors | {} |", sidecar_errors).ok();
         writeln!(f, "| Both Errors (diff codes) | {} |", both_errors).ok();
+        writeln!(
+            f,
+            "| Error Body Mismatches (same status) | {} |",
+            error_body_mismatches
+        )
+        .ok();
+        writeln!(f, "| Fork/Desync (blocks differ) | {} |", desyncs).ok();
+
+        write_clusters_markdown(&mut f, clusters);
+        write_errors_markdown(&mut f, errors);
+        write_baseline_markdown(&mut f, baseline);
 
         println!("Summary report saved to: {}", summary_filename);
     } else {
@@ -579,6 +988,13 @@ pub fn write_block_mismatch_report(
         .ok();
         writeln!(f).ok();
 
+        write_clusters_markdown(&mut f, clusters);
+        if !clusters.is_empty() {
+            writeln!(f).ok();
+            writeln!(f, "## Issues").ok();
+            writeln!(f).ok();
+        }
+
         for (block, error) in issues {
             writeln!(f, "**Block {}**:", block).ok();
             for line in error.lines() {
@@ -608,11 +1024,19 @@ pub fn write_account_mismatch_report(
     end_block: u32,
     rust_url: &str,
     sidecar_url: &str,
+    baseline: &Baseline,
+    clusters: &DiffClusters,
+    errors: &ErrorCounts,
 ) {
     let accounts_with_issues: Vec<_> = results
         .iter()
         .filter(|r| {
-            r.mismatched > 0 || r.rust_errors > 0 || r.sidecar_errors > 0 || r.both_errors > 0
+            r.mismatched > 0
+                || r.rust_errors > 0
+                || r.sidecar_errors > 0
+                || r.both_errors > 0
+                || r.error_body_mismatches > 0
+                || r.desyncs > 0
         })
         .collect();
 
@@ -644,19 +1068,22 @@ pub fn write_account_mismatch_report(
 
         writeln!(
             f,
-            "| Account | Matched | Mismatch | Rust Err | Sidecar Err | Both Err (diff codes) | Rate |"
+            "| Account | Matched | Mismatch (new) | Known | Rust Err | Sidecar Err | Both Err (diff codes) | Error Body Diff | Fork/Desync | Rate |"
         ).ok();
         writeln!(
             f,
-            "|---------|---------|----------|----------|-------------|----------------------|------|"
+            "|---------|---------|----------------|-------|----------|-------------|----------------------|-----------------|-------------|------|"
         ).ok();
 
         for result in results {
             let total = result.matched
                 + result.mismatched
+                + result.known
                 + result.rust_errors
                 + result.sidecar_errors
-                + result.both_errors;
+                + result.both_errors
+                + result.error_body_mismatches
+                + result.desyncs;
             let rate = if total > 0 {
                 (result.matched as f64 / total as f64) * 100.0
             } else {
@@ -665,7 +1092,9 @@ pub fn write_account_mismatch_report(
             let has_issues = result.mismatched > 0
                 || result.rust_errors > 0
                 || result.sidecar_errors > 0
-                || result.both_errors > 0;
+                || result.both_errors > 0
+                || result.error_body_mismatches > 0
+                || result.desyncs > 0;
             let name = if has_issues {
                 format!("**{}**", result.label)
             } else {
@@ -673,18 +1102,25 @@ pub fn write_account_mismatch_report(
             };
             writeln!(
                 f,
-                "| {} | {} | {} | {} | {} | {} | {:.1}% |",
+                "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {:.1}% |",
                 name,
                 result.matched,
                 result.mismatched,
+                result.known,
                 result.rust_errors,
                 result.sidecar_errors,
                 result.both_errors,
+                result.error_body_mismatches,
+                result.desyncs,
                 rate
             )
             .ok();
         }
 
+        write_clusters_markdown(&mut f, clusters);
+        write_errors_markdown(&mut f, errors);
+        write_baseline_markdown(&mut f, baseline);
+
         println!("Summary report saved to: {}", summary_filename);
     } else {
         eprintln!("Failed to create summary file: {}", summary_filename);
@@ -703,6 +1139,11 @@ pub fn write_account_mismatch_report(
         .ok();
         writeln!(f).ok();
 
+        write_clusters_markdown(&mut f, clusters);
+        if !clusters.is_empty() {
+            writeln!(f).ok();
+        }
+
         for result in &accounts_with_issues {
             writeln!(f, "## {} (`{}`)", result.label, result.address).ok();
             writeln!(f).ok();
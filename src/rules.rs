@@ -0,0 +1,563 @@
+//! Normalization rules for response comparison.
+//!
+//! A rules file lists per-path normalizations that the diff engine applies
+//! before comparing two values. Paths are globs over the JSON structure
+//! (e.g. `extrinsics[*].info.weight` or `**.docs`), and each rule can be scoped
+//! to specific endpoints and chains. A rule can also choose how the arrays at
+//! a path are aligned before their elements are compared, or give numeric
+//! values at a path an absolute and/or relative tolerance.
+//!
+//! Rules with `"target": "error-body"` apply only when `--error-parity` compares
+//! the bodies of two errors with the same status; `stack` is always ignored there.
+//!
+//! By default strings are compared case-insensitively everywhere; with
+//! `--strict`, only paths with an `ignore-case` rule fold case.
+//!
+//! Example `rules.json`:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "path": "extrinsics[*].info.weight", "normalize": ["ignore"], "endpoints": ["block"] },
+//!     { "path": "**.docs", "normalize": ["trim-whitespace"] },
+//!     { "path": "**", "normalize": ["numeric-string"], "chains": ["polkadot"] },
+//!     { "path": "**.balance", "normalize": ["numeric"] },
+//!     { "path": "**.hash", "normalize": ["ignore-case"] },
+//!     { "path": "extrinsics[*].info.partialFee", "tolerance": { "rel": 0.01 } },
+//!     { "path": "extrinsics[*].info.weight", "tolerance": { "abs": 1000, "rel": 0.001 } },
+//!     { "path": "extrinsics", "align": "lcs" },
+//!     { "path": "storage", "align": "unordered", "endpoints": ["pallet-storage"] },
+//!     { "path": "extrinsics[*].events", "align": { "keyed": ["method.pallet", "method.method"] } },
+//!     { "path": "message", "normalize": ["trim-whitespace"], "target": "error-body" }
+//!   ]
+//! }
+//! ```
+
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::chains::Chain;
+use crate::diff::{DiffLimits, PathSegment};
+use crate::endpoints::EndpointType;
+
+/// A normalization applied to values at (and below) a matching path
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Normalization {
+    /// Skip the path entirely: no differences are reported at or below it
+    Ignore,
+    /// Treat a numeric string and a JSON number holding the same value as equal ("123" == 123)
+    NumericString,
+    /// Lowercase `0x`-prefixed hex strings before comparing
+    LowercaseHex,
+    /// Trim leading and trailing whitespace from strings before comparing
+    TrimWhitespace,
+    /// Compare strings case-insensitively (applied everywhere unless `--strict`)
+    IgnoreCase,
+    /// Compare numbers, decimal strings and `0x` hex strings by value (up to u128).
+    /// Equal values in different encodings are reported as `EncodingOnly`.
+    Numeric,
+}
+
+impl fmt::Display for Normalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Normalization::Ignore => "ignore",
+            Normalization::NumericString => "numeric-string",
+            Normalization::LowercaseHex => "lowercase-hex",
+            Normalization::TrimWhitespace => "trim-whitespace",
+            Normalization::IgnoreCase => "ignore-case",
+            Normalization::Numeric => "numeric",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a field that is `null` on one side and absent on the other is compared
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Report as `MissingInRust` / `MissingInSidecar` (the default)
+    #[default]
+    Strict,
+    /// Treat `null` and a missing field as equal
+    Equal,
+    /// Report as a separate `NullVsMissing` diff type
+    Report,
+}
+
+impl FromStr for NullPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(NullPolicy::Strict),
+            "equal" => Ok(NullPolicy::Equal),
+            "report" => Ok(NullPolicy::Report),
+            _ => Err(format!(
+                "Unknown null policy: '{}' (expected strict, equal or report)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for NullPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NullPolicy::Strict => "strict",
+            NullPolicy::Equal => "equal",
+            NullPolicy::Report => "report",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How the two arrays at a path are aligned before their elements are compared
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArrayAlignment {
+    /// Compare elements by position (the default)
+    Index,
+    /// Align elements by longest common subsequence, so an inserted or removed
+    /// element does not shift every element after it
+    Lcs,
+    /// Match elements by the values at the given dotted key paths
+    /// (e.g. `["method.pallet", "method.method"]`)
+    Keyed(Vec<String>),
+    /// Compare the arrays as multisets: ordering is not part of the contract, and
+    /// equal elements in a different order are reported as one `OrderMismatch`
+    Unordered,
+}
+
+/// Which responses a rule applies to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleTarget {
+    /// Successful responses (the default)
+    #[default]
+    Response,
+    /// Error bodies compared by `--error-parity`
+    ErrorBody,
+}
+
+/// One segment of a path glob
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PatternSegment {
+    /// Exact object key
+    Key(String),
+    /// `*` — any single object key
+    AnyKey,
+    /// `[n]` — exact array index
+    Index(usize),
+    /// `[*]` — any array index
+    AnyIndex,
+    /// `**` — any number of segments (including none)
+    AnyDepth,
+}
+
+impl PatternSegment {
+    fn matches(&self, segment: &PathSegment) -> bool {
+        match (self, segment) {
+            (PatternSegment::Key(k), PathSegment::Key(s)) => k == s,
+            (PatternSegment::AnyKey, PathSegment::Key(_)) => true,
+            (PatternSegment::Index(i), PathSegment::Index(s)) => i == s,
+            (PatternSegment::AnyIndex, PathSegment::Index(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A JSON path glob such as `extrinsics[*].info.weight`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathPattern {
+    segments: Vec<PatternSegment>,
+}
+
+impl PathPattern {
+    /// Parse a dotted path glob. Supports `*` (any key), `[*]` (any index),
+    /// `[n]` (exact index) and `**` (any depth).
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+
+        for part in pattern.split('.') {
+            if part.is_empty() {
+                return Err(format!("Invalid path pattern '{}': empty segment", pattern));
+            }
+
+            // Split "name[0][*]" into the key and its bracketed indices
+            let (key, mut rest) = match part.find('[') {
+                Some(pos) => (&part[..pos], &part[pos..]),
+                None => (part, ""),
+            };
+
+            match key {
+                "" => {}
+                "**" => segments.push(PatternSegment::AnyDepth),
+                "*" => segments.push(PatternSegment::AnyKey),
+                _ => segments.push(PatternSegment::Key(key.to_string())),
+            }
+
+            while !rest.is_empty() {
+                let close = rest
+                    .find(']')
+                    .filter(|_| rest.starts_with('['))
+                    .ok_or_else(|| {
+                        format!("Invalid path pattern '{}': unbalanced brackets", pattern)
+                    })?;
+                let inner = &rest[1..close];
+                if inner == "*" {
+                    segments.push(PatternSegment::AnyIndex);
+                } else {
+                    let index = inner.parse::<usize>().map_err(|_| {
+                        format!("Invalid path pattern '{}': bad index '{}'", pattern, inner)
+                    })?;
+                    segments.push(PatternSegment::Index(index));
+                }
+                rest = &rest[close + 1..];
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Whether this pattern matches `path` or one of its ancestors
+    /// (a rule on a node applies to the whole subtree below it).
+    pub fn matches(&self, path: &[PathSegment]) -> bool {
+        matches_segments(&self.segments, path, true)
+    }
+
+    /// Whether this pattern matches exactly `path` (not its descendants)
+    pub fn matches_exact(&self, path: &[PathSegment]) -> bool {
+        matches_segments(&self.segments, path, false)
+    }
+}
+
+fn matches_segments(pattern: &[PatternSegment], path: &[PathSegment], prefix: bool) -> bool {
+    match pattern.split_first() {
+        None => prefix || path.is_empty(),
+        Some((PatternSegment::AnyDepth, rest)) => {
+            (0..=path.len()).any(|skip| matches_segments(rest, &path[skip..], prefix))
+        }
+        Some((segment, rest)) => match path.split_first() {
+            Some((first, path_rest)) if segment.matches(first) => {
+                matches_segments(rest, path_rest, prefix)
+            }
+            _ => false,
+        },
+    }
+}
+
+/// Rule entry as written in the rules file
+#[derive(Debug, Deserialize)]
+struct RawRule {
+    path: String,
+    #[serde(default)]
+    normalize: Vec<Normalization>,
+    #[serde(default)]
+    align: Option<ArrayAlignment>,
+    #[serde(default)]
+    tolerance: Option<Tolerance>,
+    #[serde(default)]
+    endpoints: Vec<String>,
+    #[serde(default)]
+    chains: Vec<String>,
+    #[serde(default)]
+    target: RuleTarget,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRulesFile {
+    #[serde(default)]
+    rules: Vec<RawRule>,
+}
+
+/// Allowed difference between two numeric values. Values within either bound are
+/// reported as `WithinTolerance` instead of a mismatch.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tolerance {
+    /// Maximum absolute difference
+    #[serde(default)]
+    pub abs: Option<f64>,
+    /// Maximum difference relative to the larger magnitude (0.01 = 1%)
+    #[serde(default)]
+    pub rel: Option<f64>,
+}
+
+impl Tolerance {
+    /// Whether `rust` and `sidecar` are within this tolerance
+    pub fn allows(&self, rust: f64, sidecar: f64) -> bool {
+        let delta = (rust - sidecar).abs();
+        self.abs.is_some_and(|abs| delta <= abs)
+            || self
+                .rel
+                .is_some_and(|rel| delta <= rel * rust.abs().max(sidecar.abs()))
+    }
+}
+
+/// A validated rule with its scope
+#[derive(Debug, Clone)]
+struct NormalizationRule {
+    pattern: PathPattern,
+    normalize: Vec<Normalization>,
+    align: Option<ArrayAlignment>,
+    tolerance: Option<Tolerance>,
+    /// Endpoints this rule applies to (empty = all endpoints)
+    endpoints: Vec<EndpointType>,
+    /// Chains this rule applies to (empty = all chains)
+    chains: Vec<Chain>,
+    target: RuleTarget,
+}
+
+/// All rules loaded from a rules file, across every endpoint and chain
+#[derive(Debug, Clone, Default)]
+pub struct RulesFile {
+    rules: Vec<NormalizationRule>,
+}
+
+impl RulesFile {
+    /// Load and validate a JSON rules file
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read rules file {}: {}", path.display(), e))?;
+        let raw: RawRulesFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid rules file {}: {}", path.display(), e))?;
+
+        let mut rules = Vec::new();
+        for raw_rule in raw.rules {
+            let pattern = PathPattern::parse(&raw_rule.path)?;
+            if raw_rule.normalize.is_empty()
+                && raw_rule.align.is_none()
+                && raw_rule.tolerance.is_none()
+            {
+                return Err(format!(
+                    "Rule for '{}' has none of 'normalize', 'align' or 'tolerance'",
+                    raw_rule.path
+                )
+                .into());
+            }
+            if let Some(tolerance) = raw_rule.tolerance {
+                let bounds = [tolerance.abs, tolerance.rel];
+                if bounds.iter().all(Option::is_none)
+                    || bounds.iter().flatten().any(|b| !b.is_finite() || *b < 0.0)
+                {
+                    return Err(format!(
+                        "Rule for '{}' needs a non-negative 'abs' and/or 'rel' tolerance",
+                        raw_rule.path
+                    )
+                    .into());
+                }
+            }
+            if let Some(ArrayAlignment::Keyed(ref keys)) = raw_rule.align {
+                if keys.is_empty() || keys.iter().any(|k| k.split('.').any(str::is_empty)) {
+                    return Err(format!(
+                        "Rule for '{}' has an empty or invalid key path in 'keyed'",
+                        raw_rule.path
+                    )
+                    .into());
+                }
+            }
+            let endpoints = raw_rule
+                .endpoints
+                .iter()
+                .map(|e| e.parse::<EndpointType>())
+                .collect::<Result<Vec<_>, _>>()?;
+            let chains = raw_rule
+                .chains
+                .iter()
+                .map(|c| c.parse::<Chain>())
+                .collect::<Result<Vec<_>, _>>()?;
+            rules.push(NormalizationRule {
+                pattern,
+                normalize: raw_rule.normalize,
+                align: raw_rule.align,
+                tolerance: raw_rule.tolerance,
+                endpoints,
+                chains,
+                target: raw_rule.target,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Number of rules in the file
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Select the rules that apply to one chain + endpoint combination.
+    /// Unless `strict`, strings are compared case-insensitively everywhere.
+    pub fn for_scope(
+        &self,
+        chain: &Chain,
+        endpoint: &EndpointType,
+        strict: bool,
+        null_policy: NullPolicy,
+        limits: DiffLimits,
+    ) -> DiffRules {
+        self.select(
+            RuleTarget::Response,
+            chain,
+            endpoint,
+            strict,
+            null_policy,
+            limits,
+        )
+    }
+
+    /// Select the error-body rules for one chain + endpoint combination,
+    /// plus the built-in rule ignoring `stack` (stack traces never match)
+    pub fn for_error_bodies(
+        &self,
+        chain: &Chain,
+        endpoint: &EndpointType,
+        strict: bool,
+        null_policy: NullPolicy,
+        limits: DiffLimits,
+    ) -> DiffRules {
+        let mut diff_rules = self.select(
+            RuleTarget::ErrorBody,
+            chain,
+            endpoint,
+            strict,
+            null_policy,
+            limits,
+        );
+        diff_rules.rules.push((
+            PathPattern {
+                segments: vec![
+                    PatternSegment::AnyDepth,
+                    PatternSegment::Key("stack".to_string()),
+                ],
+            },
+            vec![Normalization::Ignore],
+        ));
+        diff_rules
+    }
+
+    fn select(
+        &self,
+        target: RuleTarget,
+        chain: &Chain,
+        endpoint: &EndpointType,
+        strict: bool,
+        null_policy: NullPolicy,
+        limits: DiffLimits,
+    ) -> DiffRules {
+        let mut diff_rules = DiffRules {
+            strict,
+            null_policy,
+            limits,
+            ..DiffRules::default()
+        };
+        for rule in self
+            .rules
+            .iter()
+            .filter(|r| r.target == target)
+            .filter(|r| r.chains.is_empty() || r.chains.contains(chain))
+            .filter(|r| r.endpoints.is_empty() || r.endpoints.contains(endpoint))
+        {
+            diff_rules.count += 1;
+            if !rule.normalize.is_empty() {
+                diff_rules
+                    .rules
+                    .push((rule.pattern.clone(), rule.normalize.clone()));
+            }
+            if let Some(ref align) = rule.align {
+                diff_rules
+                    .alignments
+                    .push((rule.pattern.clone(), align.clone()));
+            }
+            if let Some(tolerance) = rule.tolerance {
+                diff_rules
+                    .tolerances
+                    .push((rule.pattern.clone(), tolerance));
+            }
+        }
+        diff_rules
+    }
+}
+
+/// Normalization rules resolved for a single scan (one chain + endpoint)
+#[derive(Debug, Clone, Default)]
+pub struct DiffRules {
+    rules: Vec<(PathPattern, Vec<Normalization>)>,
+    alignments: Vec<(PathPattern, ArrayAlignment)>,
+    tolerances: Vec<(PathPattern, Tolerance)>,
+    /// Number of file rules selected for this scan
+    count: usize,
+    /// Case-sensitive string comparison (only `ignore-case` rules fold case)
+    strict: bool,
+    /// How `null` compares against a missing field
+    null_policy: NullPolicy,
+    /// Caps on stored diffs and value sizes
+    limits: DiffLimits,
+    /// Rules for comparing error bodies (set by `--error-parity`)
+    error_body: Option<Box<DiffRules>>,
+}
+
+impl DiffRules {
+    /// Also compare the bodies of errors with the same status, using `rules`
+    pub fn with_error_body_rules(mut self, rules: DiffRules) -> Self {
+        self.error_body = Some(Box::new(rules));
+        self
+    }
+
+    /// Rules for comparing error bodies, if error parity is enabled
+    pub fn error_body_rules(&self) -> Option<&DiffRules> {
+        self.error_body.as_deref()
+    }
+
+    /// Number of rules active for this scan
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// How `null` compares against a missing field
+    pub fn null_policy(&self) -> NullPolicy {
+        self.null_policy
+    }
+
+    /// Caps on stored diffs and value sizes
+    pub fn limits(&self) -> DiffLimits {
+        self.limits
+    }
+
+    /// Array alignment for the array at exactly `path` (first matching rule wins)
+    pub fn alignment_at(&self, path: &[PathSegment]) -> Option<&ArrayAlignment> {
+        self.alignments
+            .iter()
+            .find(|(pattern, _)| pattern.matches_exact(path))
+            .map(|(_, align)| align)
+    }
+
+    /// Numeric tolerance for values at or below `path` (first matching rule wins)
+    pub fn tolerance_at(&self, path: &[PathSegment]) -> Option<Tolerance> {
+        self.tolerances
+            .iter()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map(|(_, tolerance)| *tolerance)
+    }
+
+    /// Collect the normalizations that apply at `path`
+    pub fn normalizations_at(&self, path: &[PathSegment]) -> Vec<Normalization> {
+        let mut result = Vec::new();
+        if !self.strict {
+            result.push(Normalization::IgnoreCase);
+        }
+        for (pattern, normalize) in &self.rules {
+            if pattern.matches(path) {
+                for n in normalize {
+                    if !result.contains(n) {
+                        result.push(*n);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
@@ -1,36 +1,79 @@
+use std::collections::VecDeque;
 use std::error::Error;
-use std::fs::File;
-use std::io::Write;
-use std::time::Duration;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use serde_json::Value;
+use tokio::task::JoinSet;
+
+use crate::baseline::Baseline;
 use crate::chains::Chain;
+use crate::cluster::DiffClusters;
 use crate::coverage::CoverageData;
+use crate::diff::{json_patch, JsonDiff};
 use crate::endpoints::EndpointType;
-use crate::http::{fetch_json, test_block_compare, TestResult};
+use crate::headers::HeaderCheck;
+use crate::html_report::HtmlReport;
+use crate::http::{
+    fetch_json, test_block_compare, ApiClient, ApiError, ErrorCounts, ErrorKind, RequestStats,
+    Server, TestResult,
+};
+use crate::latency::LatencySamples;
 use crate::query_params::QueryParam;
 use crate::reporting::{
     print_account_summary, print_block_summary, print_pallet_summary,
     write_account_mismatch_report, write_block_mismatch_report, write_pallet_mismatch_report,
     AccountResult, PalletResult,
 };
+use crate::rules::DiffRules;
+use crate::schema::SchemaCheck;
+use crate::sync::{BlockHashCheck, BlockSync, FinalityGate};
+
+/// Whether a result is a mismatch made up only of baselined diffs
+/// (never when diffs beyond `--max-diffs` were left unchecked)
+fn is_known_mismatch(baseline: &mut Baseline, result: &TestResult) -> bool {
+    match result {
+        TestResult::Mismatch {
+            diffs,
+            omitted_diffs: 0,
+            ..
+        } => baseline.covers_all(diffs),
+        _ => false,
+    }
+}
 
 /// Print inline diff details for non-match results
-fn log_result_inline(display_id: &str, result: &TestResult) {
+fn log_result_inline(display_id: &str, result: &TestResult, known: bool) {
     match result {
-        TestResult::Match => {}
-        TestResult::Mismatch { diffs, .. } => {
+        TestResult::Match { .. } => {}
+        TestResult::Mismatch { diffs, .. } if known => {
             println!(
-                "    {}: MISMATCH ({} diff{})",
+                "    {}: KNOWN MISMATCH ({} baselined diff{})",
                 display_id,
                 diffs.len(),
                 if diffs.len() == 1 { "" } else { "s" }
             );
+        }
+        TestResult::Mismatch {
+            diffs,
+            omitted_diffs,
+            ..
+        } => {
+            let total = diffs.len() + omitted_diffs;
+            println!(
+                "    {}: MISMATCH ({} diff{})",
+                display_id,
+                total,
+                if total == 1 { "" } else { "s" }
+            );
             // Show first 3 diffs inline for quick debugging
             for diff in diffs.iter().take(3) {
                 println!("      - {}", diff);
             }
-            if diffs.len() > 3 {
-                println!("      ... and {} more", diffs.len() - 3);
+            if total > 3 {
+                println!("      ... and {} more", total - 3);
             }
         }
         TestResult::RustError(e) => {
@@ -43,7 +86,7 @@ fn log_result_inline(display_id: &str, result: &TestResult) {
             rust_error,
             sidecar_error,
         } => {
-            if rust_error == sidecar_error {
+            if rust_error.same_as(sidecar_error) {
                 // Same error = silent match
             } else {
                 println!(
@@ -52,27 +95,171 @@ fn log_result_inline(display_id: &str, result: &TestResult) {
                 );
             }
         }
+        TestResult::ErrorBodyMismatch {
+            rust_error,
+            diffs,
+            omitted_diffs,
+            ..
+        } => {
+            let total = diffs.len() + omitted_diffs;
+            println!(
+                "    {}: ERROR BODY MISMATCH ({}, {} diff{})",
+                display_id,
+                rust_error.category(),
+                total,
+                if total == 1 { "" } else { "s" }
+            );
+            for diff in diffs.iter().take(3) {
+                println!("      - {}", diff);
+            }
+        }
+        TestResult::Desync { rust, sidecar } => {
+            println!(
+                "    {}: FORK/DESYNC (Rust API {}, Sidecar {})",
+                display_id, rust, sidecar
+            );
+        }
+    }
+}
+
+/// One request pair of a scan
+#[derive(Debug, Clone)]
+struct PairRequest {
+    /// Block number, or a composite id for extrinsic index endpoints
+    id: u64,
+    block: u32,
+    rust_api_url: String,
+    sidecar_api_url: String,
+    /// Whether this is the re-check of a result held back until the block was final
+    recheck: bool,
+}
+
+impl PairRequest {
+    fn new(id: u64, block: u32, rust_api_url: String, sidecar_api_url: String) -> Self {
+        PairRequest {
+            id,
+            block,
+            rust_api_url,
+            sidecar_api_url,
+            recheck: false,
+        }
+    }
+}
+
+/// One compared request pair: (request, result, retries and samples)
+type PairOutcome = (PairRequest, TestResult, RequestStats);
+
+/// Start comparing one request pair in the scan's window of in-flight pairs
+fn spawn_compare(
+    tasks: &mut JoinSet<PairOutcome>,
+    client: &ApiClient,
+    pair: PairRequest,
+    rules: &Arc<DiffRules>,
+    keep_responses: bool,
+    schema: Option<&Arc<SchemaCheck>>,
+    headers: Option<&Arc<HeaderCheck>>,
+    block_check: Option<&Arc<BlockHashCheck>>,
+    finality: Option<&Arc<FinalityGate>>,
+) {
+    // Responses of blocks that may still be reorganized must not be cached
+    let client = match finality {
+        Some(gate) if !gate.is_final(pair.block) => client.without_sidecar_cache(),
+        _ => client.clone(),
+    };
+    let rules = Arc::clone(rules);
+    let schema = schema.cloned();
+    let headers = headers.cloned();
+    let block_check = block_check.cloned();
+    tasks.spawn(async move {
+        // Responses of different blocks are not worth comparing
+        if let Some(check) = block_check {
+            if let BlockSync::Differ { rust, sidecar } =
+                check.check(&client, pair.block, pair.recheck).await
+            {
+                let result = TestResult::Desync {
+                    rust: *rust,
+                    sidecar: *sidecar,
+                };
+                return (pair, result, RequestStats::default());
+            }
+        }
+        let (_, result, stats) = test_block_compare(
+            client,
+            pair.rust_api_url.clone(),
+            pair.sidecar_api_url.clone(),
+            pair.id,
+            rules,
+            keep_responses,
+            schema,
+            headers,
+        )
+        .await;
+        (pair, result, stats)
+    });
+}
+
+/// Request pairs whose results were held back because their blocks were not final.
+/// They are re-checked once the rest of the scope is done and the blocks are final.
+#[derive(Debug, Default)]
+struct Rechecks {
+    held: Vec<PairRequest>,
+    ready: VecDeque<PairRequest>,
+}
+
+impl Rechecks {
+    fn hold(&mut self, pair: PairRequest) {
+        println!(
+            "    Block {}: not final yet, re-checking once finalized",
+            pair.block
+        );
+        self.held.push(pair);
+    }
+
+    /// Wait until every held block is final, then queue the held pairs again
+    async fn release(&mut self, client: &ApiClient, finality: &FinalityGate) {
+        let Some(highest) = self.held.iter().map(|pair| pair.block).max() else {
+            return;
+        };
+        finality.wait_for(client, highest).await;
+        for mut pair in self.held.drain(..) {
+            pair.recheck = true;
+            self.ready.push_back(pair);
+        }
+    }
+
+    fn next(&mut self) -> Option<PairRequest> {
+        self.ready.pop_front()
     }
 }
 
 /// Scan pallet-based endpoints (iterates over pallets and blocks)
 pub async fn scan_pallet_endpoint(
-    client: &reqwest::Client,
+    client: &ApiClient,
     chain: &Chain,
     endpoint_type: &EndpointType,
     rust_url: &str,
     sidecar_url: &str,
     start_block: u32,
     end_block: u32,
-    batch_size: u32,
-    delay_between_batches: Duration,
+    concurrency: usize,
     pallet_filter: Option<&str>,
     query_params: &[QueryParam],
+    rules: &Arc<DiffRules>,
+    baseline: &mut Baseline,
+    patch_dir: Option<&Path>,
+    spill_dir: Option<&Path>,
+    schema: Option<&Arc<SchemaCheck>>,
+    headers: Option<&Arc<HeaderCheck>>,
+    block_check: Option<&Arc<BlockHashCheck>>,
+    finality: Option<&Arc<FinalityGate>>,
     coverage: &mut CoverageData,
     total_pallets: usize,
     create_logs: bool,
     create_report: bool,
 ) -> Result<Vec<(u64, String)>, Box<dyn Error>> {
+    // Full responses are only needed for the error log or spilling to disk
+    let keep_responses = create_logs || spill_dir.is_some();
+
     // Get pallets for the selected chain
     let all_pallets = chain.pallets();
 
@@ -104,8 +291,16 @@ pub async fn scan_pallet_endpoint(
 
     // Track results per pallet
     let mut pallet_results: Vec<PalletResult> = Vec::new();
+    let mut clusters = DiffClusters::default();
+    let mut errors = ErrorCounts::default();
+    let mut total_retries = 0u32;
+    let mut latency = LatencySamples::default();
+    let mut html_report = create_report.then(HtmlReport::default);
 
     for pallet in pallets {
+        if let Some(ref mut report) = html_report {
+            report.set_scope(Some(pallet.name), None);
+        }
         println!("\n{}", "=".repeat(60));
         println!(
             "Scanning pallet: {} (index: {}) - {}",
@@ -134,29 +329,34 @@ pub async fn scan_pallet_endpoint(
             None
         };
 
-        let mut current_block = start_block;
+        let mut next_block = start_block;
         let mut matched = 0u32;
         let mut mismatched = 0u32;
+        let mut known_mismatches = 0u32;
         let mut rust_errors = 0u32;
         let mut sidecar_errors = 0u32;
         let mut both_errors = 0u32;
+        let mut error_body_mismatches = 0u32;
+        let mut desyncs = 0u32;
         let mut issues: Vec<(u64, String)> = Vec::new();
+        let mut scope_latency = LatencySamples::default();
+
+        let mut tasks = JoinSet::new();
+        let mut rechecks = Rechecks::default();
+        loop {
+            // Keep the window full: start a new pair whenever one completes
+            while tasks.len() < concurrency && next_block <= end_block {
+                let block_num = next_block;
+                next_block += 1;
+
+                if block_num.is_multiple_of(1000) || block_num == start_block {
+                    println!(
+                        "  Processing blocks {} to {}...",
+                        block_num,
+                        end_block.min(block_num.saturating_add(999))
+                    );
+                }
 
-        while current_block <= end_block {
-            let batch_end = std::cmp::min(current_block + batch_size, end_block + 1);
-            let blocks: Vec<u32> = (current_block..batch_end).collect();
-
-            if current_block % 1000 == 0 || current_block == start_block {
-                println!(
-                    "  Processing blocks {} to {}...",
-                    current_block,
-                    batch_end - 1
-                );
-            }
-
-            let mut tasks = Vec::new();
-            for block_num in blocks {
-                let client_clone = client.clone();
                 let rust_path = endpoint_type.path_with_params(Some(pallet.name), Some(block_num), query_params);
                 let sidecar_path = endpoint_type.path_with_params(Some(pallet.name), Some(block_num), query_params);
                 let rust_api_url = format!("{}{}", rust_url, rust_path);
@@ -167,44 +367,88 @@ pub async fn scan_pallet_endpoint(
                     block_num, rust_api_url, sidecar_api_url
                 );
 
-                let rust_url_clone = rust_api_url.clone();
-                tasks.push(tokio::spawn(async move {
-                    let (id, result) = test_block_compare(
-                        client_clone,
-                        rust_api_url,
-                        sidecar_api_url,
-                        block_num as u64,
-                    )
-                    .await;
-                    (id, rust_url_clone, result)
-                }));
+                spawn_compare(
+                    &mut tasks,
+                    client,
+                    PairRequest::new(block_num as u64, block_num, rust_api_url, sidecar_api_url),
+                    rules,
+                    keep_responses,
+                    schema,
+                    headers,
+                    block_check,
+                    finality,
+                );
             }
 
-            for task in tasks {
-                let (block_id, url, result) = task.await?;
-                log_result_inline(&format!("Block {}", block_id), &result);
-                process_result(
-                    block_id,
-                    &url,
-                    result,
-                    &mut matched,
-                    &mut mismatched,
-                    &mut rust_errors,
-                    &mut sidecar_errors,
-                    &mut both_errors,
-                    &mut issues,
-                    &mut error_file,
-                )?;
+            // The rest of the scope is done: re-check held results once their blocks are final
+            if let (Some(gate), true) = (finality, tasks.is_empty() && next_block > end_block) {
+                rechecks.release(client, gate).await;
+            }
+            while tasks.len() < concurrency {
+                let Some(pair) = rechecks.next() else {
+                    break;
+                };
+                spawn_compare(
+                    &mut tasks,
+                    client,
+                    pair,
+                    rules,
+                    keep_responses,
+                    schema,
+                    headers,
+                    block_check,
+                    finality,
+                );
             }
 
-            current_block = batch_end;
-
-            if current_block <= end_block {
-                tokio::time::sleep(delay_between_batches).await;
+            let (pair, result, stats) = match tasks.join_next().await {
+                Some(joined) => joined?,
+                None => break,
+            };
+            // A held pair is counted once, when it is re-checked
+            if finality.is_some_and(|gate| gate.hold(pair.block, pair.recheck, &result)) {
+                rechecks.hold(pair);
+                continue;
             }
+            total_retries += stats.retries;
+            scope_latency.add(stats.rust, stats.sidecar);
+            let (block_id, url) = (pair.id, pair.rust_api_url);
+            let known = is_known_mismatch(baseline, &result);
+            log_result_inline(&format!("Block {}", block_id), &result, known);
+            process_result(
+                block_id,
+                &url,
+                result,
+                known,
+                &mut matched,
+                &mut mismatched,
+                &mut known_mismatches,
+                &mut rust_errors,
+                &mut sidecar_errors,
+                &mut both_errors,
+                &mut error_body_mismatches,
+                &mut desyncs,
+                &mut issues,
+                &mut clusters,
+                &mut errors,
+                &mut html_report,
+                &mut error_file,
+                patch_dir,
+                spill_dir,
+                &format!("{}_{}_{}_{}", chain, endpoint_type, pallet.name, block_id),
+            )?;
         }
-
-        let total = matched + mismatched + rust_errors + sidecar_errors + both_errors;
+        // Pairs complete out of order
+        issues.sort_by_key(|(block, _)| *block);
+
+        let total = matched
+            + mismatched
+            + known_mismatches
+            + rust_errors
+            + sidecar_errors
+            + both_errors
+            + error_body_mismatches
+            + desyncs;
         let match_rate = if total > 0 {
             (matched as f64 / total as f64) * 100.0
         } else {
@@ -212,11 +456,17 @@ pub async fn scan_pallet_endpoint(
         };
 
         println!(
-            "  {} - Matched: {}/{} ({:.2}%), Mismatched: {}, Rust Errors: {}, Sidecar Errors: {}, Both Errors: {}",
-            pallet.name, matched, total, match_rate, mismatched, rust_errors, sidecar_errors, both_errors
+            "  {} - Matched: {}/{} ({:.2}%), Mismatched: {}, Known: {}, Rust Errors: {}, Sidecar Errors: {}, Both Errors: {}, Error Body Diffs: {}, Desync: {}",
+            pallet.name, matched, total, match_rate, mismatched, known_mismatches, rust_errors, sidecar_errors, both_errors, error_body_mismatches, desyncs
         );
 
-        let has_issues = mismatched > 0 || rust_errors > 0 || sidecar_errors > 0 || both_errors > 0;
+        let has_issues = mismatched > 0
+            || known_mismatches > 0
+            || rust_errors > 0
+            || sidecar_errors > 0
+            || both_errors > 0
+            || error_body_mismatches > 0
+            || desyncs > 0;
         if create_logs && has_issues {
             println!("  Issues saved to: {}", error_filename);
         } else if create_logs {
@@ -228,9 +478,13 @@ pub async fn scan_pallet_endpoint(
             index: pallet.index,
             matched,
             mismatched,
+            known: known_mismatches,
             rust_errors,
             sidecar_errors,
             both_errors,
+            error_body_mismatches,
+            desyncs,
+            latency: scope_latency.summary(),
             issues,
         });
 
@@ -243,12 +497,26 @@ pub async fn scan_pallet_endpoint(
             end_block,
             matched,
             mismatched,
+            known_mismatches,
             rust_errors,
             sidecar_errors,
             both_errors,
+            error_body_mismatches,
+            desyncs,
         );
+        endpoint_coverage.set_pallet_latency(pallet.name, scope_latency.summary());
+        latency.extend(&scope_latency);
     }
 
+    let endpoint_coverage = coverage
+        .get_chain(&chain.to_string(), total_pallets)
+        .get_endpoint(&endpoint_type.to_string(), true);
+    endpoint_coverage.set_latency(latency.summary());
+    endpoint_coverage.set_baseline_stale(baseline.stale().len());
+    endpoint_coverage.add_retries(total_retries);
+    endpoint_coverage.add_error_counts(&errors);
+    log_retries(total_retries);
+
     // Print final summary
     print_pallet_summary(
         &pallet_results,
@@ -256,10 +524,14 @@ pub async fn scan_pallet_endpoint(
         endpoint_type,
         start_block,
         end_block,
+        baseline,
+        &clusters,
+        &errors,
+        &latency.summary(),
         create_logs,
     );
 
-    // Write markdown mismatch report (only with --report flag)
+    // Write markdown and HTML mismatch reports (only with --report flag)
     if create_report {
         write_pallet_mismatch_report(
             &pallet_results,
@@ -269,7 +541,20 @@ pub async fn scan_pallet_endpoint(
             end_block,
             rust_url,
             sidecar_url,
+            baseline,
+            &clusters,
+            &errors,
         );
+        if let Some(ref report) = html_report {
+            report.write(
+                chain,
+                endpoint_type,
+                start_block,
+                end_block,
+                rust_url,
+                sidecar_url,
+            );
+        }
     }
 
     // Aggregate all issues from all pallets
@@ -283,22 +568,32 @@ pub async fn scan_pallet_endpoint(
 
 /// Scan block-based endpoints (iterates over blocks only)
 pub async fn scan_block_endpoint(
-    client: &reqwest::Client,
+    client: &ApiClient,
     chain: &Chain,
     endpoint_type: &EndpointType,
     rust_url: &str,
     sidecar_url: &str,
     start_block: u32,
     end_block: u32,
-    batch_size: u32,
-    delay_between_batches: Duration,
+    concurrency: usize,
     pallet_filter: Option<&str>,
     query_params: &[QueryParam],
+    rules: &Arc<DiffRules>,
+    baseline: &mut Baseline,
+    patch_dir: Option<&Path>,
+    spill_dir: Option<&Path>,
+    schema: Option<&Arc<SchemaCheck>>,
+    headers: Option<&Arc<HeaderCheck>>,
+    block_check: Option<&Arc<BlockHashCheck>>,
+    finality: Option<&Arc<FinalityGate>>,
     coverage: &mut CoverageData,
     total_pallets: usize,
     create_logs: bool,
     create_report: bool,
 ) -> Result<Vec<(u64, String)>, Box<dyn Error>> {
+    // Full responses are only needed for the error log or spilling to disk
+    let keep_responses = create_logs || spill_dir.is_some();
+
     println!("\n{}", "=".repeat(60));
     println!("Scanning endpoint: {}", endpoint_type);
     println!("{}", "=".repeat(60));
@@ -324,13 +619,21 @@ pub async fn scan_block_endpoint(
         None
     };
 
-    let mut current_block = start_block;
+    let mut next_block = start_block;
     let mut matched = 0u32;
     let mut mismatched = 0u32;
+    let mut known_mismatches = 0u32;
     let mut rust_errors = 0u32;
     let mut sidecar_errors = 0u32;
     let mut both_errors = 0u32;
+    let mut error_body_mismatches = 0u32;
+    let mut desyncs = 0u32;
     let mut issues: Vec<(u64, String)> = Vec::new();
+    let mut clusters = DiffClusters::default();
+    let mut errors = ErrorCounts::default();
+    let mut total_retries = 0u32;
+    let mut latency = LatencySamples::default();
+    let mut html_report = create_report.then(HtmlReport::default);
 
     // Check if this is a special extrinsic index endpoint that needs extrinsic iteration
     let is_extrinsic_idx_endpoint = matches!(
@@ -340,176 +643,235 @@ pub async fn scan_block_endpoint(
             | EndpointType::BlockExtrinsicsIdxRcBlock
     );
 
-    while current_block <= end_block {
-        let batch_end = std::cmp::min(current_block + batch_size, end_block + 1);
-        let blocks: Vec<u32> = (current_block..batch_end).collect();
-
-        if current_block % 1000 == 0 || current_block == start_block {
-            println!(
-                "  Processing blocks {} to {}...",
-                current_block,
-                batch_end - 1
-            );
-        }
-
-        let mut tasks = Vec::new();
+    // Request pairs waiting for a slot in the window
+    let mut pending: VecDeque<PairRequest> = VecDeque::new();
+    let mut tasks = JoinSet::new();
+    let mut rechecks = Rechecks::default();
+    loop {
+        // Keep the window full: start a new pair whenever one completes
+        while tasks.len() < concurrency {
+            if pending.is_empty() {
+                if next_block > end_block {
+                    break;
+                }
+                let block_num = next_block;
+                next_block += 1;
+
+                if block_num.is_multiple_of(1000) || block_num == start_block {
+                    println!(
+                        "  Processing blocks {} to {}...",
+                        block_num,
+                        end_block.min(block_num.saturating_add(999))
+                    );
+                }
 
-        if is_extrinsic_idx_endpoint {
-            // Special handling: fetch extrinsics count first, then test each index
-            for block_num in blocks {
-                // Fetch extrinsics list to get count - use appropriate URL based on endpoint type
-                let extrinsics_url = match endpoint_type {
-                    // /blocks/{blockId}/extrinsics/{index}
-                    EndpointType::BlockExtrinsicsIdx => {
-                        format!("{}/blocks/{}/extrinsics-raw", rust_url, block_num)
-                    }
-                    // /blocks/{blockId}/extrinsics/{index}?useRcBlock=true
-                    EndpointType::BlockExtrinsicsIdxRcBlock => {
-                        format!(
-                            "{}/blocks/{}/extrinsics-raw?useRcBlock=true",
-                            rust_url, block_num
-                        )
-                    }
-                    // /rc/blocks/{blockId}/extrinsics/{index}
-                    _ => {
-                        format!("{}/rc/blocks/{}/extrinsics-raw", rust_url, block_num)
-                    }
-                };
-                let extrinsics_count = match fetch_json(client, &extrinsics_url).await {
-                    Ok(json) => {
-                        // Response structure may vary - try "extrinsics" field first, then check for array at root
-                        if let Some(arr) = json.get("extrinsics").and_then(|v| v.as_array()) {
-                            arr.len()
-                        } else if let Some(arr) = json.as_array() {
-                            // Response might be a direct array
-                            arr.len()
-                        } else {
-                            // Debug: print the response keys to understand structure
-                            let keys: Vec<&str> = json
-                                .as_object()
-                                .map(|obj| obj.keys().map(|k| k.as_str()).collect())
-                                .unwrap_or_default();
+                if is_extrinsic_idx_endpoint {
+                    // Special handling: fetch extrinsics count first, then test each index
+                    // Fetch extrinsics list to get count - use appropriate URL based on endpoint type
+                    let extrinsics_url = match endpoint_type {
+                        // /blocks/{blockId}/extrinsics/{index}
+                        EndpointType::BlockExtrinsicsIdx => {
+                            format!("{}/blocks/{}/extrinsics-raw", rust_url, block_num)
+                        }
+                        // /blocks/{blockId}/extrinsics/{index}?useRcBlock=true
+                        EndpointType::BlockExtrinsicsIdxRcBlock => {
+                            format!(
+                                "{}/blocks/{}/extrinsics-raw?useRcBlock=true",
+                                rust_url, block_num
+                            )
+                        }
+                        // /rc/blocks/{blockId}/extrinsics/{index}
+                        _ => {
+                            format!("{}/rc/blocks/{}/extrinsics-raw", rust_url, block_num)
+                        }
+                    };
+                    let extrinsics_count = match fetch_json(client, Server::Rust, &extrinsics_url)
+                        .await
+                    {
+                        Ok(json) => {
+                            // Response structure may vary - try "extrinsics" field first, then check for array at root
+                            if let Some(arr) = json.get("extrinsics").and_then(|v| v.as_array()) {
+                                arr.len()
+                            } else if let Some(arr) = json.as_array() {
+                                // Response might be a direct array
+                                arr.len()
+                            } else {
+                                // Debug: print the response keys to understand structure
+                                let keys: Vec<&str> = json
+                                    .as_object()
+                                    .map(|obj| obj.keys().map(|k| k.as_str()).collect())
+                                    .unwrap_or_default();
+                                println!(
+                                    "    Block {}: Failed to parse extrinsics from response (keys: {:?}), skipping",
+                                    block_num, keys
+                                );
+                                continue;
+                            }
+                        }
+                        Err(e) => {
                             println!(
-                                "    Block {}: Failed to parse extrinsics from response (keys: {:?}), skipping",
-                                block_num, keys
+                                "    Block {}: Failed to fetch extrinsics: {}, skipping",
+                                block_num, e
                             );
+                            rust_errors += 1;
+                            issues.push((
+                                block_num as u64,
+                                format!("Failed to fetch extrinsics: {}", e),
+                            ));
                             continue;
                         }
-                    }
-                    Err(e) => {
-                        println!(
-                            "    Block {}: Failed to fetch extrinsics: {}, skipping",
-                            block_num, e
+                    };
+
+                    println!(
+                        "    Block {}: Found {} extrinsics",
+                        block_num, extrinsics_count
+                    );
+
+                    // Queue a pair for each extrinsic index
+                    for ext_idx in 0..extrinsics_count {
+                        let rust_path = endpoint_type.path_with_extrinsic_params(
+                            pallet_filter,
+                            Some(block_num),
+                            None,
+                            Some(ext_idx as u32),
+                            query_params,
+                        );
+                        let sidecar_path = endpoint_type.path_with_extrinsic_params(
+                            pallet_filter,
+                            Some(block_num),
+                            None,
+                            Some(ext_idx as u32),
+                            query_params,
                         );
-                        rust_errors += 1;
-                        issues.push((
-                            block_num as u64,
-                            format!("Failed to fetch extrinsics: {}", e),
+
+                        // Use a composite identifier: block_num * 10000 + ext_idx for tracking
+                        // Use u64 to avoid overflow with large block numbers (e.g., 1,000,000 * 10000)
+                        let composite_id = block_num as u64 * 10000 + ext_idx as u64;
+                        pending.push_back(PairRequest::new(
+                            composite_id,
+                            block_num,
+                            format!("{}{}", rust_url, rust_path),
+                            format!("{}{}", sidecar_url, sidecar_path),
                         ));
-                        continue;
                     }
-                };
-
-                println!(
-                    "    Block {}: Found {} extrinsics",
-                    block_num, extrinsics_count
-                );
-
-                // Create tasks for each extrinsic index
-                for ext_idx in 0..extrinsics_count {
-                    let client_clone = client.clone();
-                    let rust_path = endpoint_type.path_with_extrinsic_params(
+                } else {
+                    // Standard handling for other endpoints
+                    let rust_path = endpoint_type.path_with_params(
                         pallet_filter,
                         Some(block_num),
-                        None,
-                        Some(ext_idx as u32),
                         query_params,
                     );
-                    let sidecar_path = endpoint_type.path_with_extrinsic_params(
+                    let sidecar_path = endpoint_type.path_with_params(
                         pallet_filter,
                         Some(block_num),
-                        None,
-                        Some(ext_idx as u32),
                         query_params,
                     );
-                    let rust_api_url = format!("{}{}", rust_url, rust_path);
-                    let sidecar_api_url = format!("{}{}", sidecar_url, sidecar_path);
-
-                    // Use a composite identifier: block_num * 10000 + ext_idx for tracking
-                    // Use u64 to avoid overflow with large block numbers (e.g., 1,000,000 * 10000)
-                    let composite_id = block_num as u64 * 10000 + ext_idx as u64;
-
-                    let rust_url_clone = rust_api_url.clone();
-                    tasks.push(tokio::spawn(async move {
-                        let (id, result) = test_block_compare(
-                            client_clone,
-                            rust_api_url,
-                            sidecar_api_url,
-                            composite_id,
-                        )
-                        .await;
-                        (id, rust_url_clone, result)
-                    }));
+                    pending.push_back(PairRequest::new(
+                        block_num as u64,
+                        block_num,
+                        format!("{}{}", rust_url, rust_path),
+                        format!("{}{}", sidecar_url, sidecar_path),
+                    ));
                 }
             }
-        } else {
-            // Standard handling for other endpoints
-            for block_num in blocks {
-                let client_clone = client.clone();
-                let rust_path = endpoint_type.path_with_params(pallet_filter, Some(block_num), query_params);
-                let sidecar_path = endpoint_type.path_with_params(pallet_filter, Some(block_num), query_params);
-                let rust_api_url = format!("{}{}", rust_url, rust_path);
-                let sidecar_api_url = format!("{}{}", sidecar_url, sidecar_path);
 
-                let rust_url_clone = rust_api_url.clone();
-                tasks.push(tokio::spawn(async move {
-                    let (id, result) = test_block_compare(
-                        client_clone,
-                        rust_api_url,
-                        sidecar_api_url,
-                        block_num as u64,
-                    )
-                    .await;
-                    (id, rust_url_clone, result)
-                }));
+            if let Some(pair) = pending.pop_front() {
+                spawn_compare(
+                    &mut tasks,
+                    client,
+                    pair,
+                    rules,
+                    keep_responses,
+                    schema,
+                    headers,
+                    block_check,
+                    finality,
+                );
             }
         }
 
-        for task in tasks {
-            let (id, url, result) = task.await?;
-            // For extrinsic endpoints, decode the composite ID for better logging
-            let display_id = if is_extrinsic_idx_endpoint {
-                let block = id / 10000;
-                let ext_idx = id % 10000;
-                format!("Block {} Ext {}", block, ext_idx)
-            } else {
-                format!("Block {}", id)
+        // The rest of the scan is done: re-check held results once their blocks are final
+        if let (Some(gate), true) = (
+            finality,
+            tasks.is_empty() && pending.is_empty() && next_block > end_block,
+        ) {
+            rechecks.release(client, gate).await;
+        }
+        while tasks.len() < concurrency {
+            let Some(pair) = rechecks.next() else {
+                break;
             };
-
-            log_result_inline(&display_id, &result);
-
-            process_result(
-                id,
-                &url,
-                result,
-                &mut matched,
-                &mut mismatched,
-                &mut rust_errors,
-                &mut sidecar_errors,
-                &mut both_errors,
-                &mut issues,
-                &mut error_file,
-            )?;
+            spawn_compare(
+                &mut tasks,
+                client,
+                pair,
+                rules,
+                keep_responses,
+                schema,
+                headers,
+                block_check,
+                finality,
+            );
         }
 
-        current_block = batch_end;
-
-        if current_block <= end_block {
-            tokio::time::sleep(delay_between_batches).await;
+        let (pair, result, stats) = match tasks.join_next().await {
+            Some(joined) => joined?,
+            None => break,
+        };
+        // A held pair is counted once, when it is re-checked
+        if finality.is_some_and(|gate| gate.hold(pair.block, pair.recheck, &result)) {
+            rechecks.hold(pair);
+            continue;
         }
-    }
+        total_retries += stats.retries;
+        latency.add(stats.rust, stats.sidecar);
+        let (id, url) = (pair.id, pair.rust_api_url);
+        // For extrinsic endpoints, decode the composite ID for better logging
+        let display_id = if is_extrinsic_idx_endpoint {
+            let block = id / 10000;
+            let ext_idx = id % 10000;
+            format!("Block {} Ext {}", block, ext_idx)
+        } else {
+            format!("Block {}", id)
+        };
 
-    let total = matched + mismatched + rust_errors + sidecar_errors + both_errors;
+        let known = is_known_mismatch(baseline, &result);
+        log_result_inline(&display_id, &result, known);
+
+        process_result(
+            id,
+            &url,
+            result,
+            known,
+            &mut matched,
+            &mut mismatched,
+            &mut known_mismatches,
+            &mut rust_errors,
+            &mut sidecar_errors,
+            &mut both_errors,
+            &mut error_body_mismatches,
+            &mut desyncs,
+            &mut issues,
+            &mut clusters,
+            &mut errors,
+            &mut html_report,
+            &mut error_file,
+            patch_dir,
+            spill_dir,
+            &format!("{}_{}_{}", chain, endpoint_type, id),
+        )?;
+    }
+    // Pairs complete out of order
+    issues.sort_by_key(|(id, _)| *id);
+
+    let total = matched
+        + mismatched
+        + known_mismatches
+        + rust_errors
+        + sidecar_errors
+        + both_errors
+        + error_body_mismatches
+        + desyncs;
     let match_rate = if total > 0 {
         (matched as f64 / total as f64) * 100.0
     } else {
@@ -517,11 +879,17 @@ pub async fn scan_block_endpoint(
     };
 
     println!(
-        "\n{} - Matched: {}/{} ({:.2}%), Mismatched: {}, Rust Errors: {}, Sidecar Errors: {}, Both Errors: {}",
-        endpoint_type, matched, total, match_rate, mismatched, rust_errors, sidecar_errors, both_errors
+        "\n{} - Matched: {}/{} ({:.2}%), Mismatched: {}, Known: {}, Rust Errors: {}, Sidecar Errors: {}, Both Errors: {}, Error Body Diffs: {}, Desync: {}",
+        endpoint_type, matched, total, match_rate, mismatched, known_mismatches, rust_errors, sidecar_errors, both_errors, error_body_mismatches, desyncs
     );
 
-    let has_issues = mismatched > 0 || rust_errors > 0 || sidecar_errors > 0 || both_errors > 0;
+    let has_issues = mismatched > 0
+        || known_mismatches > 0
+        || rust_errors > 0
+        || sidecar_errors > 0
+        || both_errors > 0
+        || error_body_mismatches > 0
+        || desyncs > 0;
     if create_logs && has_issues {
         println!("Issues saved to: {}", error_filename);
     } else if create_logs {
@@ -536,10 +904,18 @@ pub async fn scan_block_endpoint(
         end_block,
         matched,
         mismatched,
+        known_mismatches,
         rust_errors,
         sidecar_errors,
         both_errors,
+        error_body_mismatches,
+        desyncs,
     );
+    endpoint_coverage.set_latency(latency.summary());
+    endpoint_coverage.set_baseline_stale(baseline.stale().len());
+    endpoint_coverage.add_retries(total_retries);
+    endpoint_coverage.add_error_counts(&errors);
+    log_retries(total_retries);
 
     // Print summary
     print_block_summary(
@@ -549,14 +925,21 @@ pub async fn scan_block_endpoint(
         end_block,
         matched,
         mismatched,
+        known_mismatches,
         rust_errors,
         sidecar_errors,
         both_errors,
+        error_body_mismatches,
+        desyncs,
         &issues,
+        baseline,
+        &clusters,
+        &errors,
+        &latency.summary(),
         create_logs,
     );
 
-    // Write markdown mismatch report (only with --report flag)
+    // Write markdown and HTML mismatch reports (only with --report flag)
     if create_report {
         write_block_mismatch_report(
             endpoint_type,
@@ -567,11 +950,27 @@ pub async fn scan_block_endpoint(
             sidecar_url,
             matched,
             mismatched,
+            known_mismatches,
             rust_errors,
             sidecar_errors,
             both_errors,
+            error_body_mismatches,
+            desyncs,
             &issues,
+            baseline,
+            &clusters,
+            &errors,
         );
+        if let Some(ref report) = html_report {
+            report.write(
+                chain,
+                endpoint_type,
+                start_block,
+                end_block,
+                rust_url,
+                sidecar_url,
+            );
+        }
     }
 
     Ok(issues)
@@ -579,12 +978,18 @@ pub async fn scan_block_endpoint(
 
 /// Scan runtime endpoints (single request, no iteration)
 pub async fn scan_runtime_endpoint(
-    client: &reqwest::Client,
+    client: &ApiClient,
     chain: &Chain,
     endpoint_type: &EndpointType,
     rust_url: &str,
     sidecar_url: &str,
     query_params: &[QueryParam],
+    rules: &Arc<DiffRules>,
+    baseline: &mut Baseline,
+    patch_dir: Option<&Path>,
+    spill_dir: Option<&Path>,
+    schema: Option<&Arc<SchemaCheck>>,
+    headers: Option<&Arc<HeaderCheck>>,
     coverage: &mut CoverageData,
     total_pallets: usize,
     create_logs: bool,
@@ -619,42 +1024,106 @@ pub async fn scan_runtime_endpoint(
     log_line!("  Rust API: {}", rust_api_url);
     log_line!("  Sidecar API: {}", sidecar_api_url);
 
-    let (_, result) = test_block_compare(
+    let (_, result, stats) = test_block_compare(
         client.clone(),
         rust_api_url.clone(),
         sidecar_api_url.clone(),
         0,
+        Arc::clone(rules),
+        create_logs || spill_dir.is_some(),
+        schema.cloned(),
+        headers.cloned(),
     )
     .await;
 
     // Track coverage result
     let chain_coverage = coverage.get_chain(&chain.to_string(), total_pallets);
     let endpoint_coverage = chain_coverage.get_endpoint(&endpoint_type.to_string(), false);
+    endpoint_coverage.add_retries(stats.retries);
+    if stats.retries > 0 {
+        log_line!(
+            "  Retried {} time(s) after transient failures",
+            stats.retries
+        );
+    }
+    let mut latency = LatencySamples::default();
+    latency.add(stats.rust, stats.sidecar);
+    let latency = latency.summary();
+    endpoint_coverage.set_latency(latency);
+    for line in latency.summary_lines() {
+        log_line!("{}", line);
+    }
 
     let mut issues: Vec<(u64, String)> = Vec::new();
+    let mut runtime_errors = ErrorCounts::default();
 
     match result {
-        TestResult::Match => {
+        TestResult::Match { ref equivalent } => {
             log_line!("\n  Result: MATCH - Both APIs returned identical responses");
+            if !equivalent.is_empty() {
+                log_line!("    {} equivalent difference(s):", equivalent.len());
+                for diff in equivalent.iter().take(5) {
+                    log_line!("    - {}", diff);
+                }
+            }
             endpoint_coverage.add_runtime_run(true, None);
         }
+        TestResult::Mismatch {
+            ref diffs,
+            omitted_diffs: 0,
+            ..
+        } if baseline.covers_all(diffs) => {
+            log_line!(
+                "\n  Result: KNOWN MISMATCH - {} baselined difference(s)",
+                diffs.len()
+            );
+            endpoint_coverage.add_known_run();
+        }
         TestResult::Mismatch {
             rust_response,
             sidecar_response,
             diffs,
+            omitted_diffs,
         } => {
-            log_line!("\n  Result: MISMATCH - {} difference(s) found", diffs.len());
+            let total = diffs.len() + omitted_diffs;
+            log_line!("\n  Result: MISMATCH - {} difference(s) found", total);
             // Show first few diffs in console
             for (i, diff) in diffs.iter().take(5).enumerate() {
                 log_line!("    {}. {}", i + 1, diff);
             }
-            if diffs.len() > 5 {
-                log_line!("    ... and {} more", diffs.len() - 5);
+            if total > 5 {
+                log_line!("    ... and {} more", total - 5);
             }
             endpoint_coverage.add_runtime_run(false, None);
 
             let diff_summary = diffs.iter().take(10).map(|d| d.to_string()).collect::<Vec<_>>().join("; ");
-            issues.push((0, format!("MISMATCH ({} diffs): {}", diffs.len(), diff_summary)));
+            issues.push((0, format!("MISMATCH ({} diffs): {}", total, diff_summary)));
+
+            let artifact_name = format!("{}_{}", chain, endpoint_type);
+            let spilled = match (spill_dir, &rust_response, &sidecar_response) {
+                (Some(dir), Some(rust), Some(sidecar)) => {
+                    let (rust_path, sidecar_path) =
+                        spill_responses(dir, &artifact_name, rust, sidecar)?;
+                    log_line!(
+                        "  Responses saved to: {} + {}",
+                        rust_path.display(),
+                        sidecar_path.display()
+                    );
+                    Some((rust_path, sidecar_path))
+                }
+                _ => None,
+            };
+
+            if let Some(dir) = patch_dir {
+                match write_patch(dir, &artifact_name, &diffs, omitted_diffs)? {
+                    Some(path) => {
+                        log_line!("  JSON Patch saved to: {}", path.display());
+                    }
+                    None => {
+                        log_line!("  Warning: {}", patch_skipped(omitted_diffs));
+                    }
+                }
+            }
 
             if create_logs {
                 let error_filename = format!("errors_{}_{}.log", chain, endpoint_type);
@@ -667,38 +1136,55 @@ pub async fn scan_runtime_endpoint(
                 writeln!(error_file, "# Rust API: {}", rust_api_url)?;
                 writeln!(error_file, "# Sidecar API: {}", sidecar_api_url)?;
                 writeln!(error_file, "#")?;
-                writeln!(
-                    error_file,
-                    "MISMATCH - {} difference(s) found:",
-                    diffs.len()
-                )?;
+                writeln!(error_file, "MISMATCH - {} difference(s) found:", total)?;
                 for diff in &diffs {
                     writeln!(error_file, "  - {}", diff)?;
                 }
+                if omitted_diffs > 0 {
+                    writeln!(
+                        error_file,
+                        "  ... and {} more (not stored, see --max-diffs)",
+                        omitted_diffs
+                    )?;
+                }
                 writeln!(error_file)?;
-                writeln!(
-                    error_file,
-                    "Rust API response: {}",
-                    serde_json::to_string_pretty(&rust_response)?
-                )?;
-                writeln!(
-                    error_file,
-                    "Sidecar response: {}",
-                    serde_json::to_string_pretty(&sidecar_response)?
-                )?;
+                match spilled {
+                    Some((rust_path, sidecar_path)) => {
+                        writeln!(
+                            error_file,
+                            "Rust API response: saved to {}",
+                            rust_path.display()
+                        )?;
+                        writeln!(
+                            error_file,
+                            "Sidecar response: saved to {}",
+                            sidecar_path.display()
+                        )?;
+                    }
+                    None => {
+                        write!(error_file, "Rust API response: ")?;
+                        serde_json::to_writer_pretty(&mut error_file, &rust_response)?;
+                        writeln!(error_file)?;
+                        write!(error_file, "Sidecar response: ")?;
+                        serde_json::to_writer_pretty(&mut error_file, &sidecar_response)?;
+                        writeln!(error_file)?;
+                    }
+                }
 
                 log_line!("  Details saved to: {}", error_filename);
             }
         }
         TestResult::RustError(ref err) => {
             log_line!("\n  Result: RUST API ERROR - {}", err);
-            endpoint_coverage.add_runtime_run(false, Some(err));
-            issues.push((0, format!("RUST API ERROR: {}", err)));
+            endpoint_coverage.add_runtime_run(false, Some(&err.category()));
+            runtime_errors.add("rust", err);
+            issues.push((0, format!("RUST API ERROR ({}): {}", err.category(), err)));
         }
         TestResult::SidecarError(ref err) => {
             log_line!("\n  Result: SIDECAR ERROR - {}", err);
-            endpoint_coverage.add_runtime_run(false, Some(err));
-            issues.push((0, format!("SIDECAR ERROR: {}", err)));
+            endpoint_coverage.add_runtime_run(false, Some(&err.category()));
+            runtime_errors.add("sidecar", err);
+            issues.push((0, format!("SIDECAR ERROR ({}): {}", err.category(), err)));
         }
         TestResult::BothError {
             ref rust_error,
@@ -707,11 +1193,54 @@ pub async fn scan_runtime_endpoint(
             log_line!("\n  Result: BOTH APIS ERROR");
             log_line!("    Rust: {}", rust_error);
             log_line!("    Sidecar: {}", sidecar_error);
-            endpoint_coverage.add_runtime_run(false, Some(rust_error));
-            if rust_error != sidecar_error {
+            endpoint_coverage.add_runtime_run(false, Some(&rust_error.category()));
+            if !rust_error.same_as(sidecar_error) {
+                runtime_errors.add("rust", rust_error);
+                runtime_errors.add("sidecar", sidecar_error);
                 issues.push((0, format!("BOTH ERRORS (diff codes) - Rust: {}, Sidecar: {}", rust_error, sidecar_error)));
             }
         }
+        TestResult::ErrorBodyMismatch {
+            ref rust_error,
+            ref sidecar_error,
+            ref diffs,
+            omitted_diffs,
+        } => {
+            let total = diffs.len() + omitted_diffs;
+            log_line!(
+                "\n  Result: ERROR BODY MISMATCH - {} difference(s) between the {} bodies",
+                total,
+                rust_error.category()
+            );
+            for (i, diff) in diffs.iter().take(5).enumerate() {
+                log_line!("    {}. {}", i + 1, diff);
+            }
+            log_line!("    Rust: {}", rust_error);
+            log_line!("    Sidecar: {}", sidecar_error);
+            endpoint_coverage.add_error_body_run();
+            let diff_summary = diffs
+                .iter()
+                .take(10)
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join("; ");
+            issues.push((
+                0,
+                format!(
+                    "ERROR BODY MISMATCH ({}, {} diffs): {}",
+                    rust_error.category(),
+                    total,
+                    diff_summary
+                ),
+            ));
+        }
+        TestResult::Desync { .. } => unreachable!("runtime endpoints are not pinned to a block"),
+    }
+
+    endpoint_coverage.add_error_counts(&runtime_errors);
+    endpoint_coverage.set_baseline_stale(baseline.stale().len());
+    for entry in baseline.stale() {
+        log_line!("  Stale baseline entry: {}", entry);
     }
 
     if create_logs {
@@ -723,21 +1252,31 @@ pub async fn scan_runtime_endpoint(
 
 /// Scan account-based endpoints (iterates over accounts and blocks)
 pub async fn scan_account_endpoint(
-    client: &reqwest::Client,
+    client: &ApiClient,
     chain: &Chain,
     endpoint_type: &EndpointType,
     rust_url: &str,
     sidecar_url: &str,
     start_block: u32,
     end_block: u32,
-    batch_size: u32,
-    delay_between_batches: Duration,
+    concurrency: usize,
     query_params: &[QueryParam],
+    rules: &Arc<DiffRules>,
+    baseline: &mut Baseline,
+    patch_dir: Option<&Path>,
+    spill_dir: Option<&Path>,
+    schema: Option<&Arc<SchemaCheck>>,
+    headers: Option<&Arc<HeaderCheck>>,
+    block_check: Option<&Arc<BlockHashCheck>>,
+    finality: Option<&Arc<FinalityGate>>,
     coverage: &mut CoverageData,
     total_pallets: usize,
     create_logs: bool,
     create_report: bool,
 ) -> Result<Vec<(u64, String)>, Box<dyn Error>> {
+    // Full responses are only needed for the error log or spilling to disk
+    let keep_responses = create_logs || spill_dir.is_some();
+
     // Get test accounts for the selected chain (use stash accounts for staking endpoints)
     let accounts = if endpoint_type.is_staking() {
         if !chain.has_staking_accounts() {
@@ -776,8 +1315,16 @@ pub async fn scan_account_endpoint(
 
     // Track results per account
     let mut account_results: Vec<AccountResult> = Vec::new();
+    let mut clusters = DiffClusters::default();
+    let mut errors = ErrorCounts::default();
+    let mut total_retries = 0u32;
+    let mut latency = LatencySamples::default();
+    let mut html_report = create_report.then(HtmlReport::default);
 
     for account in accounts {
+        if let Some(ref mut report) = html_report {
+            report.set_scope(None, Some(account.label));
+        }
         println!("\n{}", "=".repeat(60));
         println!(
             "Scanning account: {} ({}) - {}",
@@ -810,29 +1357,34 @@ pub async fn scan_account_endpoint(
             None
         };
 
-        let mut current_block = start_block;
+        let mut next_block = start_block;
         let mut matched = 0u32;
         let mut mismatched = 0u32;
+        let mut known_mismatches = 0u32;
         let mut rust_errors = 0u32;
         let mut sidecar_errors = 0u32;
         let mut both_errors = 0u32;
+        let mut error_body_mismatches = 0u32;
+        let mut desyncs = 0u32;
         let mut issues: Vec<(u64, String)> = Vec::new();
+        let mut scope_latency = LatencySamples::default();
+
+        let mut tasks = JoinSet::new();
+        let mut rechecks = Rechecks::default();
+        loop {
+            // Keep the window full: start a new pair whenever one completes
+            while tasks.len() < concurrency && next_block <= end_block {
+                let block_num = next_block;
+                next_block += 1;
+
+                if block_num.is_multiple_of(1000) || block_num == start_block {
+                    println!(
+                        "  Processing blocks {} to {}...",
+                        block_num,
+                        end_block.min(block_num.saturating_add(999))
+                    );
+                }
 
-        while current_block <= end_block {
-            let batch_end = std::cmp::min(current_block + batch_size, end_block + 1);
-            let blocks: Vec<u32> = (current_block..batch_end).collect();
-
-            if current_block % 1000 == 0 || current_block == start_block {
-                println!(
-                    "  Processing blocks {} to {}...",
-                    current_block,
-                    batch_end - 1
-                );
-            }
-
-            let mut tasks = Vec::new();
-            for block_num in blocks {
-                let client_clone = client.clone();
                 let rust_path =
                     endpoint_type.path_with_account_params(None, Some(block_num), Some(account.address), query_params);
                 let sidecar_path =
@@ -845,44 +1397,94 @@ pub async fn scan_account_endpoint(
                     block_num, rust_api_url, sidecar_api_url
                 );
 
-                let rust_url_clone = rust_api_url.clone();
-                tasks.push(tokio::spawn(async move {
-                    let (id, result) = test_block_compare(
-                        client_clone,
-                        rust_api_url,
-                        sidecar_api_url,
-                        block_num as u64,
-                    )
-                    .await;
-                    (id, rust_url_clone, result)
-                }));
+                spawn_compare(
+                    &mut tasks,
+                    client,
+                    PairRequest::new(block_num as u64, block_num, rust_api_url, sidecar_api_url),
+                    rules,
+                    keep_responses,
+                    schema,
+                    headers,
+                    block_check,
+                    finality,
+                );
             }
 
-            for task in tasks {
-                let (block_id, url, result) = task.await?;
-                log_result_inline(&format!("Block {}", block_id), &result);
-                process_result(
-                    block_id,
-                    &url,
-                    result,
-                    &mut matched,
-                    &mut mismatched,
-                    &mut rust_errors,
-                    &mut sidecar_errors,
-                    &mut both_errors,
-                    &mut issues,
-                    &mut error_file,
-                )?;
+            // The rest of the scope is done: re-check held results once their blocks are final
+            if let (Some(gate), true) = (finality, tasks.is_empty() && next_block > end_block) {
+                rechecks.release(client, gate).await;
+            }
+            while tasks.len() < concurrency {
+                let Some(pair) = rechecks.next() else {
+                    break;
+                };
+                spawn_compare(
+                    &mut tasks,
+                    client,
+                    pair,
+                    rules,
+                    keep_responses,
+                    schema,
+                    headers,
+                    block_check,
+                    finality,
+                );
             }
 
-            current_block = batch_end;
-
-            if current_block <= end_block {
-                tokio::time::sleep(delay_between_batches).await;
+            let (pair, result, stats) = match tasks.join_next().await {
+                Some(joined) => joined?,
+                None => break,
+            };
+            // A held pair is counted once, when it is re-checked
+            if finality.is_some_and(|gate| gate.hold(pair.block, pair.recheck, &result)) {
+                rechecks.hold(pair);
+                continue;
             }
+            total_retries += stats.retries;
+            scope_latency.add(stats.rust, stats.sidecar);
+            let (block_id, url) = (pair.id, pair.rust_api_url);
+            let known = is_known_mismatch(baseline, &result);
+            log_result_inline(&format!("Block {}", block_id), &result, known);
+            process_result(
+                block_id,
+                &url,
+                result,
+                known,
+                &mut matched,
+                &mut mismatched,
+                &mut known_mismatches,
+                &mut rust_errors,
+                &mut sidecar_errors,
+                &mut both_errors,
+                &mut error_body_mismatches,
+                &mut desyncs,
+                &mut issues,
+                &mut clusters,
+                &mut errors,
+                &mut html_report,
+                &mut error_file,
+                patch_dir,
+                spill_dir,
+                &format!(
+                    "{}_{}_account_{}_{}",
+                    chain,
+                    endpoint_type,
+                    account.label.replace(" ", "_"),
+                    block_id
+                ),
+            )?;
         }
-
-        let total = matched + mismatched + rust_errors + sidecar_errors + both_errors;
+        // Pairs complete out of order
+        issues.sort_by_key(|(block, _)| *block);
+
+        let total = matched
+            + mismatched
+            + known_mismatches
+            + rust_errors
+            + sidecar_errors
+            + both_errors
+            + error_body_mismatches
+            + desyncs;
         let match_rate = if total > 0 {
             (matched as f64 / total as f64) * 100.0
         } else {
@@ -890,11 +1492,17 @@ pub async fn scan_account_endpoint(
         };
 
         println!(
-            "  {} - Matched: {}/{} ({:.2}%), Mismatched: {}, Rust Errors: {}, Sidecar Errors: {}, Both Errors: {}",
-            account.label, matched, total, match_rate, mismatched, rust_errors, sidecar_errors, both_errors
+            "  {} - Matched: {}/{} ({:.2}%), Mismatched: {}, Known: {}, Rust Errors: {}, Sidecar Errors: {}, Both Errors: {}, Error Body Diffs: {}, Desync: {}",
+            account.label, matched, total, match_rate, mismatched, known_mismatches, rust_errors, sidecar_errors, both_errors, error_body_mismatches, desyncs
         );
 
-        let has_issues = mismatched > 0 || rust_errors > 0 || sidecar_errors > 0 || both_errors > 0;
+        let has_issues = mismatched > 0
+            || known_mismatches > 0
+            || rust_errors > 0
+            || sidecar_errors > 0
+            || both_errors > 0
+            || error_body_mismatches > 0
+            || desyncs > 0;
         if create_logs && has_issues {
             println!("  Issues saved to: {}", error_filename);
         } else if create_logs {
@@ -906,9 +1514,13 @@ pub async fn scan_account_endpoint(
             address: account.address.to_string(),
             matched,
             mismatched,
+            known: known_mismatches,
             rust_errors,
             sidecar_errors,
             both_errors,
+            error_body_mismatches,
+            desyncs,
+            latency: scope_latency.summary(),
             issues,
         });
 
@@ -921,12 +1533,26 @@ pub async fn scan_account_endpoint(
             end_block,
             matched,
             mismatched,
+            known_mismatches,
             rust_errors,
             sidecar_errors,
             both_errors,
+            error_body_mismatches,
+            desyncs,
         );
+        endpoint_coverage.set_account_latency(account.label, scope_latency.summary());
+        latency.extend(&scope_latency);
     }
 
+    let endpoint_coverage = coverage
+        .get_chain(&chain.to_string(), total_pallets)
+        .get_endpoint(&endpoint_type.to_string(), false);
+    endpoint_coverage.set_latency(latency.summary());
+    endpoint_coverage.set_baseline_stale(baseline.stale().len());
+    endpoint_coverage.add_retries(total_retries);
+    endpoint_coverage.add_error_counts(&errors);
+    log_retries(total_retries);
+
     // Print final summary
     print_account_summary(
         &account_results,
@@ -934,10 +1560,14 @@ pub async fn scan_account_endpoint(
         endpoint_type,
         start_block,
         end_block,
+        baseline,
+        &clusters,
+        &errors,
+        &latency.summary(),
         create_logs,
     );
 
-    // Write markdown mismatch report (only with --report flag)
+    // Write markdown and HTML mismatch reports (only with --report flag)
     if create_report {
         write_account_mismatch_report(
             &account_results,
@@ -947,7 +1577,20 @@ pub async fn scan_account_endpoint(
             end_block,
             rust_url,
             sidecar_url,
+            baseline,
+            &clusters,
+            &errors,
         );
+        if let Some(ref report) = html_report {
+            report.write(
+                chain,
+                endpoint_type,
+                start_block,
+                end_block,
+                rust_url,
+                sidecar_url,
+            );
+        }
     }
 
     // Aggregate all issues from all accounts
@@ -959,38 +1602,159 @@ pub async fn scan_account_endpoint(
     Ok(all_issues)
 }
 
+/// Write the full responses of a mismatch to `{dir}/{name}.rust.json` and
+/// `{dir}/{name}.sidecar.json`, streaming instead of building the JSON text in memory
+pub fn spill_responses(
+    dir: &Path,
+    name: &str,
+    rust_response: &Value,
+    sidecar_response: &Value,
+) -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+    let rust_path = dir.join(format!("{}.rust.json", name));
+    let sidecar_path = dir.join(format!("{}.sidecar.json", name));
+    serde_json::to_writer_pretty(BufWriter::new(File::create(&rust_path)?), rust_response)?;
+    serde_json::to_writer_pretty(
+        BufWriter::new(File::create(&sidecar_path)?),
+        sidecar_response,
+    )?;
+    Ok((rust_path, sidecar_path))
+}
+
+/// Write the JSON Patch of a mismatch to `{dir}/{name}.patch.json`. A patch built from
+/// a diff list capped by `--max-diffs` would be incomplete, so none is written when
+/// diffs were omitted (returns `None`).
+pub fn write_patch(
+    dir: &Path,
+    name: &str,
+    diffs: &[JsonDiff],
+    omitted_diffs: usize,
+) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    if omitted_diffs > 0 {
+        return Ok(None);
+    }
+    let path = dir.join(format!("{}.patch.json", name));
+    fs::write(&path, serde_json::to_string_pretty(&json_patch(diffs))?)?;
+    Ok(Some(path))
+}
+
+/// Warning for a mismatch whose patch was skipped by `write_patch`
+pub fn patch_skipped(omitted_diffs: usize) -> String {
+    format!(
+        "JSON Patch not written: {} diff(s) beyond --max-diffs were not stored",
+        omitted_diffs
+    )
+}
+
+/// Write the status, timing, headers and body of a failed request to the error log
+fn write_api_error(f: &mut File, label: &str, err: &ApiError) -> std::io::Result<()> {
+    writeln!(
+        f,
+        "  {} error: kind={}, status={}, elapsed={}ms, retries={}",
+        label,
+        err.kind.name(),
+        err.kind.status().map_or("-".to_string(), |s| s.to_string()),
+        err.elapsed.as_millis(),
+        err.retries
+    )?;
+    if let ErrorKind::Http { ref headers, .. } = err.kind {
+        for (name, value) in headers {
+            writeln!(f, "    {}: {}", name, value)?;
+        }
+    }
+    if let Some(body) = err.body() {
+        write!(f, "  {} error body: ", label)?;
+        serde_json::to_writer_pretty(&mut *f, body)?;
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+/// Print how many transient request failures were retried during a scan
+fn log_retries(retries: u32) {
+    if retries > 0 {
+        println!(
+            "Retried requests: {} (transient failures, not counted as errors)",
+            retries
+        );
+    }
+}
+
 /// Process a test result and update counters
 fn process_result(
     block_num: u64,
     rust_api_url: &str,
     result: TestResult,
+    known: bool,
     matched: &mut u32,
     mismatched: &mut u32,
+    known_mismatches: &mut u32,
     rust_errors: &mut u32,
     sidecar_errors: &mut u32,
     both_errors: &mut u32,
+    error_body_mismatches: &mut u32,
+    desyncs: &mut u32,
     issues: &mut Vec<(u64, String)>,
+    clusters: &mut DiffClusters,
+    errors: &mut ErrorCounts,
+    html_report: &mut Option<HtmlReport>,
     error_file: &mut Option<File>,
+    patch_dir: Option<&Path>,
+    spill_dir: Option<&Path>,
+    artifact_name: &str,
 ) -> Result<(), Box<dyn Error>> {
+    if let Some(report) = html_report {
+        report.add(block_num, rust_api_url, &result, known);
+    }
+    if result.counts_as_match() {
+        *matched += 1;
+    }
     match result {
-        TestResult::Match => {
-            *matched += 1;
+        TestResult::Match { equivalent } => {
+            clusters.add(block_num, rust_api_url, &equivalent);
+            if let (Some(ref mut f), false) = (error_file, equivalent.is_empty()) {
+                writeln!(
+                    f,
+                    "Block {}: MATCH [{}] ({} equivalent diffs)",
+                    block_num,
+                    rust_api_url,
+                    equivalent.len()
+                )?;
+                for diff in &equivalent {
+                    writeln!(f, "    - {}", diff)?;
+                }
+            }
+        }
+        TestResult::Mismatch { diffs, .. } if known => {
+            // Only baselined diffs: counted separately and kept out of the issue list
+            *known_mismatches += 1;
+            if let Some(ref mut f) = error_file {
+                writeln!(
+                    f,
+                    "Block {}: KNOWN MISMATCH [{}] ({} baselined diffs)",
+                    block_num,
+                    rust_api_url,
+                    diffs.len()
+                )?;
+            }
         }
         TestResult::Mismatch {
             rust_response,
             sidecar_response,
             diffs,
+            omitted_diffs,
         } => {
             *mismatched += 1;
+            clusters.add(block_num, rust_api_url, &diffs);
+            let total = diffs.len() + omitted_diffs;
             // Create a summary of the differences
             let diff_summary = if diffs.is_empty() {
                 "unknown differences".to_string()
-            } else if diffs.len() == 1 {
+            } else if total == 1 {
                 format!("1 difference: {}", diffs[0])
-            } else if diffs.len() <= 5 {
+            } else if total <= 5 {
                 format!(
                     "{} differences:\n    - {}",
-                    diffs.len(),
+                    total,
                     diffs
                         .iter()
                         .map(|d| d.to_string())
@@ -1000,7 +1764,7 @@ fn process_result(
             } else {
                 format!(
                     "{} differences (showing first 10):\n    - {}",
-                    diffs.len(),
+                    total,
                     diffs
                         .iter()
                         .take(10)
@@ -1010,40 +1774,77 @@ fn process_result(
                 )
             };
             let msg = format!("MISMATCH [{}] - {}", rust_api_url, diff_summary);
+            let spilled = match (spill_dir, &rust_response, &sidecar_response) {
+                (Some(dir), Some(rust), Some(sidecar)) => {
+                    Some(spill_responses(dir, artifact_name, rust, sidecar)?)
+                }
+                _ => None,
+            };
             if let Some(ref mut f) = error_file {
                 writeln!(f, "Block {}: MISMATCH", block_num)?;
-                writeln!(f, "  Differences ({}):", diffs.len())?;
+                writeln!(f, "  Differences ({}):", total)?;
                 for diff in &diffs {
                     writeln!(f, "    - {}", diff)?;
                 }
+                if omitted_diffs > 0 {
+                    writeln!(
+                        f,
+                        "    ... and {} more (not stored, see --max-diffs)",
+                        omitted_diffs
+                    )?;
+                }
                 writeln!(f)?;
-                writeln!(
-                    f,
-                    "  Rust API response: {}",
-                    serde_json::to_string_pretty(&rust_response)?
-                )?;
-                writeln!(
-                    f,
-                    "  Sidecar response: {}",
-                    serde_json::to_string_pretty(&sidecar_response)?
-                )?;
+                match spilled {
+                    Some((rust_path, sidecar_path)) => {
+                        writeln!(f, "  Rust API response: saved to {}", rust_path.display())?;
+                        writeln!(f, "  Sidecar response: saved to {}", sidecar_path.display())?;
+                    }
+                    None => {
+                        write!(f, "  Rust API response: ")?;
+                        serde_json::to_writer_pretty(&mut *f, &rust_response)?;
+                        writeln!(f)?;
+                        write!(f, "  Sidecar response: ")?;
+                        serde_json::to_writer_pretty(&mut *f, &sidecar_response)?;
+                        writeln!(f)?;
+                    }
+                }
                 writeln!(f)?;
             }
+            let msg = match patch_dir {
+                Some(dir) if write_patch(dir, artifact_name, &diffs, omitted_diffs)?.is_none() => {
+                    format!("{}\n    ({})", msg, patch_skipped(omitted_diffs))
+                }
+                _ => msg,
+            };
             issues.push((block_num, msg));
         }
         TestResult::RustError(err) => {
             *rust_errors += 1;
-            let msg = format!("RUST API ERROR [{}]: {}", rust_api_url, err);
+            errors.add("rust", &err);
+            let msg = format!(
+                "RUST API ERROR ({}) [{}]: {}",
+                err.category(),
+                rust_api_url,
+                err
+            );
             if let Some(ref mut f) = error_file {
                 writeln!(f, "Block {}: {}", block_num, msg)?;
+                write_api_error(f, "Rust API", &err)?;
             }
             issues.push((block_num, msg));
         }
         TestResult::SidecarError(err) => {
             *sidecar_errors += 1;
-            let msg = format!("SIDECAR ERROR [{}]: {}", rust_api_url, err);
+            errors.add("sidecar", &err);
+            let msg = format!(
+                "SIDECAR ERROR ({}) [{}]: {}",
+                err.category(),
+                rust_api_url,
+                err
+            );
             if let Some(ref mut f) = error_file {
                 writeln!(f, "Block {}: {}", block_num, msg)?;
+                write_api_error(f, "Sidecar", &err)?;
             }
             issues.push((block_num, msg));
         }
@@ -1051,21 +1852,79 @@ fn process_result(
             rust_error,
             sidecar_error,
         } => {
-            // Both APIs erroring counts as a match in summary
-            *matched += 1;
             // Only record in details when error codes differ
-            if rust_error != sidecar_error {
+            if !rust_error.same_as(&sidecar_error) {
                 *both_errors += 1;
+                errors.add("rust", &rust_error);
+                errors.add("sidecar", &sidecar_error);
                 let msg = format!(
                     "BOTH ERRORS [{}] (different codes) - Rust: {}, Sidecar: {}",
                     rust_api_url, rust_error, sidecar_error
                 );
                 if let Some(ref mut f) = error_file {
                     writeln!(f, "Block {}: {}", block_num, msg)?;
+                    write_api_error(f, "Rust API", &rust_error)?;
+                    write_api_error(f, "Sidecar", &sidecar_error)?;
                 }
                 issues.push((block_num, msg));
             }
         }
+        TestResult::ErrorBodyMismatch {
+            rust_error,
+            sidecar_error,
+            diffs,
+            omitted_diffs,
+        } => {
+            *error_body_mismatches += 1;
+            let total = diffs.len() + omitted_diffs;
+            let msg = format!(
+                "ERROR BODY MISMATCH ({}) [{}] - {} difference(s):\n    - {}",
+                rust_error.category(),
+                rust_api_url,
+                total,
+                diffs
+                    .iter()
+                    .take(10)
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n    - ")
+            );
+            if let Some(ref mut f) = error_file {
+                writeln!(
+                    f,
+                    "Block {}: ERROR BODY MISMATCH ({}) [{}]",
+                    block_num,
+                    rust_error.category(),
+                    rust_api_url
+                )?;
+                writeln!(f, "  Differences ({}):", total)?;
+                for diff in &diffs {
+                    writeln!(f, "    - {}", diff)?;
+                }
+                if omitted_diffs > 0 {
+                    writeln!(
+                        f,
+                        "    ... and {} more (not stored, see --max-diffs)",
+                        omitted_diffs
+                    )?;
+                }
+                write_api_error(f, "Rust API", &rust_error)?;
+                write_api_error(f, "Sidecar", &sidecar_error)?;
+            }
+            issues.push((block_num, msg));
+        }
+        TestResult::Desync { rust, sidecar } => {
+            // Not a serialization difference: the servers' nodes have different blocks
+            *desyncs += 1;
+            let msg = format!(
+                "FORK/DESYNC [{}] - Rust API block {}, Sidecar block {}",
+                rust_api_url, rust, sidecar
+            );
+            if let Some(ref mut f) = error_file {
+                writeln!(f, "Block {}: {}", block_num, msg)?;
+            }
+            issues.push((block_num, msg));
+        }
     }
     Ok(())
 }
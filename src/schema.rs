@@ -0,0 +1,651 @@
+//! Response schema inference and validation.
+//!
+//! While scanning, every successful Sidecar response is folded into an inferred
+//! schema for the scanned endpoint: which JSON types each field takes, how often
+//! each object field is present, and the set of values of enum-like string fields
+//! (short identifiers such as `"Normal"` or `"ApplyExtrinsic"`). Schemas are keyed
+//! by chain, endpoint and query params and persisted to a JSON file, so later runs
+//! can check Rust responses against them even when Sidecar is unavailable.
+//!
+//! Rust responses are validated against the schema loaded at the start of the run.
+//! When the run also infers the schema, Rust responses are written to a temporary
+//! file until the end of the scan, then read back and validated against the schema
+//! including this run's Sidecar responses.
+//! Structural violations (wrong type, missing required field, unexpected field,
+//! unknown enum value) are reported separately from value mismatches.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::chains::Chain;
+use crate::cluster::{Group, GroupKey, GroupSet};
+use crate::diff::PathSegment;
+use crate::endpoints::EndpointType;
+use crate::query_params::QueryParam;
+use crate::rules::{DiffRules, Normalization};
+
+/// Distinct values above which a string field is no longer enum-like
+const MAX_ENUM_VALUES: usize = 16;
+
+/// Strings longer than this are never enum values
+const MAX_ENUM_VALUE_LEN: usize = 32;
+
+/// Observations needed before a field is treated as required or enum-like
+const MIN_SAMPLES: u64 = 20;
+
+/// Inferred schema of one location in a response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaNode {
+    /// Number of values observed at this location
+    pub samples: u64,
+    /// Observed JSON type name -> count
+    #[serde(default)]
+    pub types: BTreeMap<String, u64>,
+    /// Object fields; a field's `samples` is the number of objects containing it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, SchemaNode>,
+    /// Schema of array elements (all elements merged)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<SchemaNode>>,
+    /// Distinct string values, while the field still looks enum-like
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub strings: BTreeSet<String>,
+    /// Set once a string was seen that rules out an enum (too many values, not an identifier)
+    #[serde(default)]
+    pub free_form: bool,
+}
+
+impl SchemaNode {
+    /// Fold one value into the schema
+    pub fn observe(&mut self, value: &Value) {
+        self.samples += 1;
+        *self.types.entry(type_name(value).to_string()).or_default() += 1;
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    self.fields.entry(key.clone()).or_default().observe(child);
+                }
+            }
+            Value::Array(items) => {
+                let node = self.items.get_or_insert_with(Default::default);
+                for item in items {
+                    node.observe(item);
+                }
+            }
+            Value::String(s) => self.observe_string(s),
+            _ => {}
+        }
+    }
+
+    fn observe_string(&mut self, s: &str) {
+        if self.free_form {
+            return;
+        }
+        if !is_identifier(s) {
+            self.free_form = true;
+            self.strings.clear();
+            return;
+        }
+        self.strings.insert(s.to_string());
+        if self.strings.len() > MAX_ENUM_VALUES {
+            self.free_form = true;
+            self.strings.clear();
+        }
+    }
+
+    /// Number of objects observed at this location
+    fn objects(&self) -> u64 {
+        self.types.get("object").copied().unwrap_or(0)
+    }
+
+    /// Whether string values are restricted to the observed set
+    fn is_enum(&self) -> bool {
+        !self.free_form
+            && !self.strings.is_empty()
+            && self.types.get("string").copied().unwrap_or(0) >= MIN_SAMPLES
+    }
+
+    /// Check a value against the schema, appending violations
+    fn validate(
+        &self,
+        value: &Value,
+        path: &mut Vec<PathSegment>,
+        rules: &DiffRules,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if is_ignored(path, rules) {
+            return;
+        }
+        let actual = type_name(value);
+        if !self.types.contains_key(actual) {
+            let expected: Vec<&str> = self.types.keys().map(String::as_str).collect();
+            out.push(SchemaViolation::new(
+                path,
+                ViolationKind::WrongType,
+                format!("expected {}, found {}", expected.join("|"), actual),
+            ));
+            return;
+        }
+
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    path.push(PathSegment::Key(key.clone()));
+                    match self.fields.get(key) {
+                        Some(node) => node.validate(child, path, rules, out),
+                        None if !is_ignored(path, rules) => out.push(SchemaViolation::new(
+                            path,
+                            ViolationKind::UnexpectedField,
+                            format!("field never seen in Sidecar ({})", type_name(child)),
+                        )),
+                        None => {}
+                    }
+                    path.pop();
+                }
+                let objects = self.objects();
+                if objects >= MIN_SAMPLES {
+                    for (key, node) in &self.fields {
+                        if node.samples < objects || map.contains_key(key) {
+                            continue;
+                        }
+                        path.push(PathSegment::Key(key.clone()));
+                        if !is_ignored(path, rules) {
+                            out.push(SchemaViolation::new(
+                                path,
+                                ViolationKind::MissingField,
+                                format!("present in all {} Sidecar samples", objects),
+                            ));
+                        }
+                        path.pop();
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(ref node) = self.items {
+                    for (i, item) in items.iter().enumerate() {
+                        path.push(PathSegment::Index(i));
+                        node.validate(item, path, rules, out);
+                        path.pop();
+                    }
+                }
+            }
+            Value::String(s) if self.is_enum() && !self.strings.contains(s) => {
+                let known: Vec<&str> = self.strings.iter().map(String::as_str).collect();
+                out.push(SchemaViolation::new(
+                    path,
+                    ViolationKind::UnknownEnumValue,
+                    format!("\"{}\" not in {{{}}}", s, known.join(", ")),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Short JSON type name of a value
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Whether a string looks like an enum variant name (e.g. "Normal", "ApplyExtrinsic")
+fn is_identifier(s: &str) -> bool {
+    s.len() <= MAX_ENUM_VALUE_LEN
+        && s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether an `ignore` rule covers the path
+fn is_ignored(path: &[PathSegment], rules: &DiffRules) -> bool {
+    rules
+        .normalizations_at(path)
+        .contains(&Normalization::Ignore)
+}
+
+/// Kind of structural violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Value type never seen at this path in Sidecar
+    WrongType,
+    /// Field present in every Sidecar sample is missing
+    MissingField,
+    /// Field never seen in Sidecar
+    UnexpectedField,
+    /// String outside the set of values seen for an enum-like field
+    UnknownEnumValue,
+}
+
+/// One violation found in a Rust response
+#[derive(Debug, Clone)]
+pub struct SchemaViolation {
+    /// Path with array indices replaced by `[*]`
+    pub path: String,
+    pub kind: ViolationKind,
+    /// Detail of the violation (of the first occurrence, in a group)
+    pub detail: String,
+}
+
+impl SchemaViolation {
+    fn new(path: &[PathSegment], kind: ViolationKind, detail: String) -> Self {
+        let mut normalized = String::new();
+        for segment in path {
+            match segment {
+                PathSegment::Key(key) => {
+                    if !normalized.is_empty() {
+                        normalized.push('.');
+                    }
+                    normalized.push_str(key);
+                }
+                PathSegment::Index(_) => normalized.push_str("[*]"),
+            }
+        }
+        if normalized.is_empty() {
+            normalized.push_str("(root)");
+        }
+        SchemaViolation {
+            path: normalized,
+            kind,
+            detail,
+        }
+    }
+}
+
+impl GroupKey for SchemaViolation {
+    fn label(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?}: {}", self.path, self.kind, self.detail)
+    }
+}
+
+/// All responses with one violation (same path and kind) over a scan
+pub type ViolationGroup = Group<SchemaViolation>;
+
+/// Validation results collected over one scan
+#[derive(Debug, Default)]
+struct ViolationSet {
+    validated: u64,
+    invalid: u64,
+    /// Grouped by path and kind
+    groups: GroupSet<SchemaViolation>,
+}
+
+/// Root schema file structure, persisted as JSON
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaFile {
+    /// Schema key ("chain/endpoint" or "chain/endpoint?params") -> inferred schema
+    #[serde(default)]
+    pub schemas: BTreeMap<String, SchemaNode>,
+}
+
+impl SchemaFile {
+    /// Load schemas from file (empty if the file does not exist)
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if path.exists() {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read schema file {}: {}", path.display(), e))?;
+            let data: SchemaFile = serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid schema file {}: {}", path.display(), e))?;
+            Ok(data)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Save schemas to file
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Schema key for a scan; query params change the response shape, so they are part of it
+    pub fn key(chain: &Chain, endpoint: &EndpointType, query_params: &[QueryParam]) -> String {
+        let mut key = format!("{}/{}", chain, endpoint);
+        if !query_params.is_empty() {
+            let params: Vec<String> = query_params.iter().map(|p| p.to_string()).collect();
+            key.push('?');
+            key.push_str(&params.join(","));
+        }
+        key
+    }
+}
+
+/// A Rust response held for validation at the end of the scan,
+/// stored as a `[block, url, body]` line
+#[derive(Debug, Deserialize)]
+struct PendingResponse {
+    block: u64,
+    url: String,
+    body: Value,
+}
+
+/// Rust responses held until the end of the scan, as JSON Lines in a temporary
+/// file so memory does not grow with the scan
+#[derive(Debug)]
+struct PendingFile {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+}
+
+impl PendingFile {
+    fn create(dir: &Path, key: &str) -> Result<Self, String> {
+        let name: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = dir.join(format!(
+            "schema_pending_{}_{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let file = File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(PendingFile {
+            path,
+            writer: Some(BufWriter::new(file)),
+        })
+    }
+
+    fn push(&mut self, block: u64, url: &str, body: &Value) {
+        let Some(ref mut writer) = self.writer else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *writer, &(block, url, body))
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"));
+        if let Err(e) = written {
+            eprintln!(
+                "Warning: failed to write {}, schema validation stops here: {}",
+                self.path.display(),
+                e
+            );
+            self.writer = None;
+        }
+    }
+
+    /// Finish writing and read the held responses back, in order
+    fn drain(&mut self) -> Result<impl Iterator<Item = PendingResponse>, String> {
+        if let Some(mut writer) = self.writer.take() {
+            writer
+                .flush()
+                .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        }
+        let file = File::open(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        let path = self.path.clone();
+        Ok(BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(move |line| match serde_json::from_str(&line) {
+                Ok(response) => Some(response),
+                Err(e) => {
+                    eprintln!(
+                        "Warning: skipping a corrupt line in {}: {}",
+                        path.display(),
+                        e
+                    );
+                    None
+                }
+            }))
+    }
+}
+
+impl Drop for PendingFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Schema inference and validation state shared by the requests of one scan
+#[derive(Debug)]
+pub struct SchemaCheck {
+    key: String,
+    /// Schema as loaded at the start of the run, used for validation when not inferring
+    reference: Option<SchemaNode>,
+    /// Schema being extended with this run's Sidecar responses (when inferring)
+    learned: Option<Mutex<SchemaNode>>,
+    /// Rust responses awaiting validation against the learned schema (when inferring and validating)
+    pending: Option<Mutex<PendingFile>>,
+    violations: Mutex<ViolationSet>,
+}
+
+impl SchemaCheck {
+    /// Set up inference and/or validation for one schema key. Validating without
+    /// inferring needs a stored schema; when inferring too, Rust responses are
+    /// held in a file in `pending_dir` and validated at the end of the scan,
+    /// against the schema inferred so far.
+    pub fn new(
+        store: &SchemaFile,
+        key: String,
+        infer: bool,
+        validate: bool,
+        pending_dir: &Path,
+    ) -> Result<Self, String> {
+        let stored = store.schemas.get(&key);
+        if validate && !infer && stored.is_none() {
+            return Err(format!(
+                "No stored schema for {}; run with --infer-schema first, or add it to this run",
+                key
+            ));
+        }
+        let pending = if validate && infer {
+            Some(Mutex::new(PendingFile::create(pending_dir, &key)?))
+        } else {
+            None
+        };
+        Ok(SchemaCheck {
+            reference: if validate && !infer {
+                stored.cloned()
+            } else {
+                None
+            },
+            learned: infer.then(|| Mutex::new(stored.cloned().unwrap_or_default())),
+            pending,
+            violations: Mutex::new(ViolationSet::default()),
+            key,
+        })
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Number of responses the validation schema was inferred from (so far, when
+    /// it is inferred in this run)
+    pub fn reference_samples(&self) -> Option<u64> {
+        match (&self.reference, &self.pending, &self.learned) {
+            (Some(reference), _, _) => Some(reference.samples),
+            (None, Some(_), Some(learned)) => Some(learned.lock().unwrap().samples),
+            _ => None,
+        }
+    }
+
+    /// Whether Rust responses are being validated
+    pub fn is_validating(&self) -> bool {
+        self.reference.is_some() || self.pending.is_some()
+    }
+
+    /// Whether Rust responses are held until the end of the scan
+    pub fn is_deferred(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Fold a Sidecar response into the inferred schema
+    pub fn observe(&self, sidecar: &Value) {
+        if let Some(ref learned) = self.learned {
+            learned.lock().unwrap().observe(sidecar);
+        }
+    }
+
+    /// Validate a Rust response and record its violations. When the schema is
+    /// inferred in this run, the response is held until `validate_pending`.
+    pub fn validate(&self, block: u64, url: &str, rust: &Value, rules: &DiffRules) {
+        if let Some(ref pending) = self.pending {
+            pending.lock().unwrap().push(block, url, rust);
+            return;
+        }
+        let Some(ref reference) = self.reference else {
+            return;
+        };
+        self.record(block, url, rust, reference, rules);
+    }
+
+    /// Validate the held Rust responses against the schema inferred in this run
+    pub fn validate_pending(&self, rules: &DiffRules) {
+        let (Some(pending), Some(learned)) = (&self.pending, &self.learned) else {
+            return;
+        };
+        let reference = learned.lock().unwrap().clone();
+        // No Sidecar sample at all: there is nothing to validate against
+        if reference.samples == 0 {
+            return;
+        }
+        let mut pending = pending.lock().unwrap();
+        let responses = match pending.drain() {
+            Ok(responses) => responses,
+            Err(e) => {
+                eprintln!("Warning: schema validation skipped: {}", e);
+                return;
+            }
+        };
+        for response in responses {
+            self.record(
+                response.block,
+                &response.url,
+                &response.body,
+                &reference,
+                rules,
+            );
+        }
+    }
+
+    /// Check a Rust response against `reference` and record its violations
+    fn record(
+        &self,
+        block: u64,
+        url: &str,
+        rust: &Value,
+        reference: &SchemaNode,
+        rules: &DiffRules,
+    ) {
+        let mut found = Vec::new();
+        reference.validate(rust, &mut Vec::new(), rules, &mut found);
+
+        let mut set = self.violations.lock().unwrap();
+        set.validated += 1;
+        if found.is_empty() {
+            return;
+        }
+        set.invalid += 1;
+        set.groups.add(
+            block,
+            url,
+            found
+                .into_iter()
+                .map(|v| (format!("{}|{:?}", v.path, v.kind), v)),
+        );
+    }
+
+    /// Violation groups ordered by occurrence count (most frequent first)
+    pub fn sorted_violations(&self) -> Vec<ViolationGroup> {
+        let set = self.violations.lock().unwrap();
+        set.groups.sorted().into_iter().cloned().collect()
+    }
+
+    /// (validated responses, responses with at least one violation)
+    pub fn counts(&self) -> (u64, u64) {
+        let set = self.violations.lock().unwrap();
+        (set.validated, set.invalid)
+    }
+
+    /// Store the schema inferred during this run
+    pub fn store_learned(&self, store: &mut SchemaFile) {
+        if let Some(ref learned) = self.learned {
+            let node = learned.lock().unwrap().clone();
+            if node.samples > 0 {
+                store.schemas.insert(self.key.clone(), node);
+            }
+        }
+    }
+
+    pub fn print_summary(&self) {
+        let (validated, invalid) = self.counts();
+        println!("\n{}", "=".repeat(90));
+        println!("                              SCHEMA VALIDATION");
+        println!("{}", "=".repeat(90));
+        println!(
+            "Schema: {} ({} Sidecar samples)",
+            self.key,
+            self.reference_samples().unwrap_or(0)
+        );
+        println!(
+            "Rust responses validated: {}, with structural violations: {}",
+            validated, invalid
+        );
+        let groups = self.sorted_violations();
+        if groups.is_empty() {
+            return;
+        }
+        println!("\nViolations (by responses affected):");
+        for group in &groups {
+            println!(
+                "  {:>6}x  {} (first at block {})",
+                group.occurrences, group, group.first_block
+            );
+            for url in &group.example_urls {
+                println!("           {}", url);
+            }
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let (validated, invalid) = self.counts();
+        let mut md = String::new();
+        md.push_str(&format!("# Schema Validation — `{}`\n\n", self.key));
+        md.push_str(&format!(
+            "- **Sidecar samples**: {}\n- **Rust responses validated**: {}\n- **With structural violations**: {}\n\n",
+            self.reference_samples().unwrap_or(0),
+            validated,
+            invalid
+        ));
+        let groups = self.sorted_violations();
+        if groups.is_empty() {
+            md.push_str("No structural violations.\n");
+            return md;
+        }
+        md.push_str("| Responses | Path | Violation | Detail | First Block | Example |\n");
+        md.push_str("|-----------|------|-----------|--------|-------------|---------|\n");
+        for group in &groups {
+            md.push_str(&format!(
+                "| {} | `{}` | {:?} | {} | {} | {} |\n",
+                group.occurrences,
+                group.key.path,
+                group.key.kind,
+                group.key.detail.replace('|', "\\|"),
+                group.first_block,
+                group.example_urls.first().map_or("", String::as_str)
+            ));
+        }
+        md
+    }
+}
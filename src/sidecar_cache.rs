@@ -0,0 +1,364 @@
+//! Persistent cache of Sidecar responses.
+//!
+//! Sidecar is the slow side of every scan, and its response for a finalized block
+//! never changes at a given Sidecar commit. With `--sidecar-cache`, successful
+//! Sidecar responses of block-scoped scans are stored on disk keyed by (Sidecar
+//! commit, chain, full URL) and served from there on later runs, so re-checking a
+//! new Rust API build only queries the Rust server.
+//!
+//! Layout: `<dir>/<commit>/<chain>/<url hash>.json.gz`, each file holding the URL
+//! and the stored response. Entries can be invalidated per commit or per chain,
+//! and the cache is kept under a size cap by evicting the least recently used
+//! entries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::cassette::StoredResponse;
+use crate::chains::Chain;
+use crate::http::RawResponse;
+
+/// Evicting stops once the cache is back under this fraction of the cap
+const PRUNE_TARGET: f64 = 0.9;
+
+/// One cached response
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    response: StoredResponse,
+}
+
+/// The Sidecar cache of one (commit, chain) scope
+#[derive(Debug)]
+pub struct SidecarCache {
+    root: PathBuf,
+    /// `<root>/<commit>/<chain>`
+    scope: PathBuf,
+    max_bytes: u64,
+    /// Bytes on disk across all scopes
+    size: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stored: AtomicU64,
+    /// Responses not stored because the cache was full
+    skipped: AtomicU64,
+}
+
+impl SidecarCache {
+    /// Open the cache scope for a Sidecar commit and chain, pruning the cache to `max_bytes` first
+    pub fn open(
+        root: &Path,
+        commit: &str,
+        chain: &Chain,
+        max_bytes: u64,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let scope = root.join(sanitize(commit)).join(chain.to_string());
+        fs::create_dir_all(&scope).map_err(|e| {
+            format!(
+                "Failed to create cache directory {}: {}",
+                scope.display(),
+                e
+            )
+        })?;
+        let cache = SidecarCache {
+            root: root.to_path_buf(),
+            scope,
+            max_bytes,
+            size: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stored: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+        };
+        cache.prune();
+        Ok(cache)
+    }
+
+    /// Cached response for a URL. A hit refreshes the entry's position in the eviction order.
+    pub fn get(&self, url: &str) -> Option<RawResponse> {
+        let path = self.entry_path(url);
+        let entry = File::open(&path)
+            .ok()
+            .and_then(|file| {
+                serde_json::from_reader::<_, CacheEntry>(BufReader::new(GzDecoder::new(file))).ok()
+            })
+            // A hash collision or a damaged file is treated as a miss
+            .filter(|entry| entry.url == url);
+        match entry {
+            Some(entry) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                if let Ok(file) = File::options().write(true).open(&path) {
+                    file.set_modified(SystemTime::now()).ok();
+                }
+                Some(entry.response.to_raw())
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Store a successful response; nothing is stored once the cache is full
+    pub fn put(&self, url: &str, raw: &RawResponse) {
+        if !(200..300).contains(&raw.status) {
+            return;
+        }
+        if self.size.load(Ordering::Relaxed) >= self.max_bytes {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let path = self.entry_path(url);
+        let entry = CacheEntry {
+            url: url.to_string(),
+            response: StoredResponse::new(raw),
+        };
+        // Write to a temporary file first so concurrent readers never see a partial entry
+        let tmp = path.with_extension("tmp");
+        let previous = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let written = File::create(&tmp).and_then(|file| {
+            let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+            serde_json::to_writer(&mut encoder, &entry)?;
+            encoder.finish()?.flush()?;
+            fs::rename(&tmp, &path)?;
+            fs::metadata(&path)
+        });
+        match written {
+            Ok(metadata) => {
+                self.size.fetch_add(metadata.len(), Ordering::Relaxed);
+                self.size.fetch_sub(previous, Ordering::Relaxed);
+                self.stored.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                fs::remove_file(&tmp).ok();
+                eprintln!(
+                    "Warning: failed to write cache entry {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Evict the least recently used entries (across all scopes) until the cache
+    /// fits under the cap, and refresh the size
+    pub fn prune(&self) {
+        let mut files = Vec::new();
+        collect_entries(&self.root, &mut files);
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        if total > self.max_bytes {
+            files.sort_by_key(|(_, _, modified)| *modified);
+            let target = (self.max_bytes as f64 * PRUNE_TARGET) as u64;
+            for (path, len, _) in &files {
+                if total <= target {
+                    break;
+                }
+                if fs::remove_file(path).is_ok() {
+                    total -= len;
+                }
+            }
+        }
+        self.size.store(total, Ordering::Relaxed);
+    }
+
+    /// One-line statistics of this run
+    pub fn summary(&self) -> String {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        let rate = if lookups > 0 {
+            hits as f64 / lookups as f64 * 100.0
+        } else {
+            0.0
+        };
+        let mut summary = format!(
+            "{} hits, {} misses ({:.1}% hit rate), {} stored",
+            hits,
+            misses,
+            rate,
+            self.stored.load(Ordering::Relaxed)
+        );
+        let skipped = self.skipped.load(Ordering::Relaxed);
+        if skipped > 0 {
+            summary.push_str(&format!(", {} not stored (cache full)", skipped));
+        }
+        summary.push_str(&format!(
+            "; {} on disk (cap {})",
+            format_size(self.size.load(Ordering::Relaxed)),
+            format_size(self.max_bytes)
+        ));
+        summary
+    }
+
+    pub fn scope(&self) -> &Path {
+        &self.scope
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.scope.join(format!("{:016x}.json.gz", fnv1a(url)))
+    }
+}
+
+/// Entries and bytes per (commit, chain) scope
+pub fn usage(root: &Path) -> BTreeMap<(String, String), (u64, u64)> {
+    let mut usage = BTreeMap::new();
+    for commit in subdirs(root) {
+        for chain in subdirs(&commit) {
+            let mut files = Vec::new();
+            collect_entries(&chain, &mut files);
+            let key = (file_name(&commit), file_name(&chain));
+            usage.insert(
+                key,
+                (
+                    files.len() as u64,
+                    files.iter().map(|(_, len, _)| len).sum(),
+                ),
+            );
+        }
+    }
+    usage
+}
+
+/// Print the entries and size of every scope
+pub fn print_usage(root: &Path) {
+    let usage = usage(root);
+    println!("Sidecar cache: {}", root.display());
+    if usage.is_empty() {
+        println!("  (empty)");
+        return;
+    }
+    println!(
+        "  {:<14} {:<22} {:>10} {:>12}",
+        "Commit", "Chain", "Entries", "Size"
+    );
+    let (mut entries, mut bytes) = (0, 0);
+    for ((commit, chain), (count, size)) in &usage {
+        println!(
+            "  {:<14} {:<22} {:>10} {:>12}",
+            commit,
+            chain,
+            count,
+            format_size(*size)
+        );
+        entries += count;
+        bytes += size;
+    }
+    println!(
+        "  {:<14} {:<22} {:>10} {:>12}",
+        "Total",
+        "",
+        entries,
+        format_size(bytes)
+    );
+}
+
+/// Remove cached entries: `all`, every scope of a commit, or every scope of a chain.
+/// Returns the (entries, bytes) removed.
+pub fn clear(root: &Path, target: &str) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    let usage = usage(root);
+    let matching: Vec<_> = usage
+        .iter()
+        .filter(|((commit, chain), _)| target == "all" || commit == target || chain == target)
+        .collect();
+    if matching.is_empty() {
+        return Err(format!(
+            "No cached Sidecar responses for '{}' in {} (use all, a commit or a chain)",
+            target,
+            root.display()
+        )
+        .into());
+    }
+    let (mut entries, mut bytes) = (0, 0);
+    for ((commit, chain), (count, size)) in matching {
+        let dir = root.join(commit).join(chain);
+        fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))?;
+        // Drop the commit directory once its last chain is gone
+        fs::remove_dir(root.join(commit)).ok();
+        entries += count;
+        bytes += size;
+    }
+    Ok((entries, bytes))
+}
+
+/// Human-readable byte size
+pub fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// (path, size, last modified) of all cache entries below `dir`
+fn collect_entries(dir: &Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_entries(&path, files);
+        } else if path.to_string_lossy().ends_with(".json.gz") {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((path, metadata.len(), modified));
+        }
+    }
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|read_dir| {
+            read_dir
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Commit ids come from git or the command line; keep them safe as a directory name
+fn sanitize(commit: &str) -> String {
+    commit
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 64-bit FNV-1a hash; stable across builds, unlike the std hasher
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,437 @@
+//! Sync checks between the nodes behind the two servers.
+//!
+//! Both servers are expected to follow the same chain, but they are often backed
+//! by different nodes. When Sidecar's node lags behind or follows another fork, the
+//! last blocks of a scan turn into spurious Sidecar errors or mismatches. Before a
+//! block scan, the finalized heads of both servers are compared and the scan ends at
+//! the lower one, and the blocks at both ends of the range are checked to be the
+//! same block on both servers. With `--verify-block-hashes`, every block of a scan
+//! is checked this way before its data is compared, so blocks the servers disagree
+//! on are reported as fork/desync instead of as mismatches.
+//!
+//! Blocks above the finalized head may still be reorganized. When a scan reaches
+//! past it, results other than a match for those blocks are held back and
+//! re-checked once both servers have finalized them, so only differences that
+//! persist are reported.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::http::{fetch_json, ApiClient, Server, TestResult};
+
+/// Finalized heads further apart than this are reported (`--max-head-gap`)
+pub const DEFAULT_MAX_HEAD_GAP: u32 = 10;
+
+/// How often the finalized heads are polled while waiting for finality
+const FINALITY_POLL_INTERVAL: Duration = Duration::from_secs(6);
+
+/// A block header as reported by one server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockIdentity {
+    pub number: u32,
+    /// Block hash, when the server includes it (Sidecar's header response does not)
+    pub hash: Option<String>,
+    pub parent_hash: String,
+    pub state_root: String,
+    pub extrinsics_root: String,
+    /// Scalar values of the digest logs (pre-runtime, consensus and seal items), in
+    /// order with object fields by key and strings lowercased, so encodings that
+    /// only differ in hex case or field names still compare equal
+    pub digest_logs: Vec<String>,
+}
+
+impl BlockIdentity {
+    /// Parse a `/blocks/{n}/header` response
+    fn from_header(json: &Value) -> Option<Self> {
+        let field = |name: &str| json[name].as_str().map(str::to_string);
+        Some(BlockIdentity {
+            number: field("number")?.parse().ok()?,
+            hash: field("hash"),
+            parent_hash: field("parentHash")?,
+            state_root: field("stateRoot")?,
+            extrinsics_root: field("extrinsicsRoot")?,
+            digest_logs: {
+                let mut values = Vec::new();
+                collect_scalars(&json["digest"]["logs"], &mut values);
+                values
+            },
+        })
+    }
+
+    /// Whether both describe the same block. The block hash is the hash of the
+    /// header, so when a hash is missing, every header field is compared: number,
+    /// parent hash, roots and digest logs. Blocks of competing forks can share all
+    /// but the digest (e.g. a different seal), so the digest is part of it.
+    pub fn same_block(&self, other: &BlockIdentity) -> bool {
+        match (&self.hash, &other.hash) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                self.number == other.number
+                    && self.parent_hash == other.parent_hash
+                    && self.state_root == other.state_root
+                    && self.extrinsics_root == other.extrinsics_root
+                    && self.digest_logs == other.digest_logs
+            }
+        }
+    }
+}
+
+/// Append the scalar values of a JSON value in order, object fields by key (strings lowercased)
+fn collect_scalars(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Null => {}
+        Value::String(s) => out.push(s.to_lowercase()),
+        Value::Array(items) => items.iter().for_each(|item| collect_scalars(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_scalars(item, out)),
+        other => out.push(other.to_string()),
+    }
+}
+
+impl fmt::Display for BlockIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hash {
+            Some(ref hash) => write!(f, "#{} {}", self.number, hash),
+            None => write!(
+                f,
+                "#{} (parent {}, state root {})",
+                self.number, self.parent_hash, self.state_root
+            ),
+        }
+    }
+}
+
+/// Fetch and parse a block header from one server
+async fn fetch_header(
+    client: &ApiClient,
+    server: Server,
+    url: &str,
+) -> Result<BlockIdentity, String> {
+    let json = fetch_json(client, server, url)
+        .await
+        .map_err(|e| format!("Failed to get {}: {}", url, e))?;
+    BlockIdentity::from_header(&json)
+        .ok_or_else(|| format!("Missing or invalid header fields in response from {}", url))
+}
+
+/// Finalized head of one server. Never served from the Sidecar cache: heads move.
+pub async fn get_finalized_head(
+    client: &ApiClient,
+    server: Server,
+    base_url: &str,
+) -> Result<BlockIdentity, String> {
+    let url = format!("{}/blocks/head/header", base_url);
+    fetch_header(&client.without_sidecar_cache(), server, &url).await
+}
+
+/// Header of block `number` on one server
+pub async fn get_block_identity(
+    client: &ApiClient,
+    server: Server,
+    base_url: &str,
+    number: u32,
+) -> Result<BlockIdentity, String> {
+    let url = format!("{}/blocks/{}/header", base_url, number);
+    fetch_header(client, server, &url).await
+}
+
+/// Finalized heads as far as they could be read
+#[derive(Debug, Clone, Copy)]
+pub struct Heads {
+    /// Default end of a block scan: the lower finalized head of the two servers,
+    /// or the Rust API's alone when Sidecar's is unknown
+    pub latest: u32,
+    /// Head finalized on both servers (None when Sidecar's head is unknown)
+    pub finalized: Option<u32>,
+}
+
+/// Read the finalized heads of both servers. Warns when the heads are more than
+/// `max_gap` blocks apart. When Sidecar's head cannot be determined, the Rust
+/// API's head is the default end, but no block is known to be final on both.
+pub async fn get_latest_block(
+    client: &ApiClient,
+    rust_url: &str,
+    sidecar_url: &str,
+    max_gap: u32,
+) -> Result<Heads, Box<dyn std::error::Error>> {
+    let (rust, sidecar) = tokio::join!(
+        get_finalized_head(client, Server::Rust, rust_url),
+        get_finalized_head(client, Server::Sidecar, sidecar_url)
+    );
+    let rust = rust?.number;
+    let sidecar = match sidecar {
+        Ok(head) => head.number,
+        Err(e) => {
+            eprintln!(
+                "Warning: could not determine Sidecar's finalized head ({}); no block is known to be final on both servers",
+                e
+            );
+            return Ok(Heads {
+                latest: rust,
+                finalized: None,
+            });
+        }
+    };
+    println!("Finalized heads: Rust API #{}, Sidecar #{}", rust, sidecar);
+
+    let gap = rust.abs_diff(sidecar);
+    if gap > max_gap {
+        let (behind, ahead) = if sidecar < rust {
+            ("Sidecar", "Rust API")
+        } else {
+            ("Rust API", "Sidecar")
+        };
+        eprintln!(
+            "Warning: {}'s node is {} blocks behind the {}'s (more than --max-head-gap {}); scanning up to #{}",
+            behind,
+            gap,
+            ahead,
+            max_gap,
+            rust.min(sidecar)
+        );
+    }
+    Ok(Heads {
+        latest: rust.min(sidecar),
+        finalized: Some(rust.min(sidecar)),
+    })
+}
+
+/// Check that both servers report the same block at each height, warning about
+/// every height where they differ. Returns whether all checked heights agree.
+pub async fn verify_block_identities(
+    client: &ApiClient,
+    rust_url: &str,
+    sidecar_url: &str,
+    heights: &[u32],
+) -> bool {
+    // The heights may be above finality, so the headers are not cached
+    let client = &client.without_sidecar_cache();
+    let mut heights = heights.to_vec();
+    heights.dedup();
+    let mut agree = true;
+    for number in heights {
+        let (rust, sidecar) = tokio::join!(
+            get_block_identity(client, Server::Rust, rust_url, number),
+            get_block_identity(client, Server::Sidecar, sidecar_url, number)
+        );
+        match (rust, sidecar) {
+            (Ok(rust), Ok(sidecar)) if rust.same_block(&sidecar) => {}
+            (Ok(rust), Ok(sidecar)) => {
+                agree = false;
+                eprintln!(
+                    "Warning: the servers disagree on block #{} (Rust API {}, Sidecar {}); \
+                     their nodes are on different chains or forks, so results for this range \
+                     are not meaningful",
+                    number, rust, sidecar
+                );
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                agree = false;
+                eprintln!("Warning: could not verify block #{}: {}", number, e);
+            }
+        }
+    }
+    agree
+}
+
+/// Outcome of checking one block on both servers
+#[derive(Debug, Clone)]
+pub enum BlockSync {
+    Same,
+    /// The servers' nodes have different blocks at this height
+    Differ {
+        rust: Box<BlockIdentity>,
+        sidecar: Box<BlockIdentity>,
+    },
+    /// A header could not be fetched; the block is compared as usual
+    Unverified,
+}
+
+/// Per-block cross-verification (`--verify-block-hashes`). Each block is resolved
+/// once per run, however many pallets or accounts are scanned at that height.
+#[derive(Debug)]
+pub struct BlockHashCheck {
+    rust_url: String,
+    sidecar_url: String,
+    blocks: Mutex<HashMap<u32, Arc<OnceCell<BlockSync>>>>,
+}
+
+impl BlockHashCheck {
+    pub fn new(rust_url: &str, sidecar_url: &str) -> Self {
+        BlockHashCheck {
+            rust_url: rust_url.to_string(),
+            sidecar_url: sidecar_url.to_string(),
+            blocks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check a block, or wait for the check already running for it. With `refresh`,
+    /// an earlier outcome is discarded (a block that was not final may have changed).
+    pub async fn check(&self, client: &ApiClient, number: u32, refresh: bool) -> BlockSync {
+        let cell = {
+            let mut blocks = self.blocks.lock().unwrap();
+            if refresh {
+                blocks.remove(&number);
+            }
+            Arc::clone(blocks.entry(number).or_default())
+        };
+        cell.get_or_init(|| async {
+            let (rust, sidecar) = tokio::join!(
+                get_block_identity(client, Server::Rust, &self.rust_url, number),
+                get_block_identity(client, Server::Sidecar, &self.sidecar_url, number)
+            );
+            match (rust, sidecar) {
+                (Ok(rust), Ok(sidecar)) if rust.same_block(&sidecar) => BlockSync::Same,
+                (Ok(rust), Ok(sidecar)) => BlockSync::Differ {
+                    rust: Box::new(rust),
+                    sidecar: Box::new(sidecar),
+                },
+                _ => BlockSync::Unverified,
+            }
+        })
+        .await
+        .clone()
+    }
+
+    /// One-line statistics, e.g. "1000 blocks checked, 3 differ, 1 unverified"
+    pub fn summary(&self) -> String {
+        let blocks = self.blocks.lock().unwrap();
+        let (mut differ, mut unverified) = (0, 0);
+        for sync in blocks.values().filter_map(|cell| cell.get()) {
+            match sync {
+                BlockSync::Same => {}
+                BlockSync::Differ { .. } => differ += 1,
+                BlockSync::Unverified => unverified += 1,
+            }
+        }
+        let mut summary = format!("{} blocks checked, {} differ", blocks.len(), differ);
+        if unverified > 0 {
+            summary.push_str(&format!(
+                ", {} unverified (header not available on both servers)",
+                unverified
+            ));
+        }
+        summary
+    }
+}
+
+/// Holds back results of blocks above the finalized head until both servers have
+/// finalized them. Created when a scan ends above the finalized head.
+#[derive(Debug)]
+pub struct FinalityGate {
+    rust_url: String,
+    sidecar_url: String,
+    /// Lower finalized head of both servers, as last seen
+    finalized: AtomicU32,
+    /// How long to wait for finality before re-checking anyway (`--finality-timeout`)
+    timeout: Duration,
+    /// Set when waiting timed out; nothing is held back after that
+    gave_up: AtomicBool,
+    held: AtomicU64,
+    persisted: AtomicU64,
+}
+
+impl FinalityGate {
+    pub fn new(rust_url: &str, sidecar_url: &str, finalized: u32, timeout: Duration) -> Self {
+        FinalityGate {
+            rust_url: rust_url.to_string(),
+            sidecar_url: sidecar_url.to_string(),
+            finalized: AtomicU32::new(finalized),
+            timeout,
+            gave_up: AtomicBool::new(false),
+            held: AtomicU64::new(0),
+            persisted: AtomicU64::new(0),
+        }
+    }
+
+    pub fn finalized(&self) -> u32 {
+        self.finalized.load(Ordering::Relaxed)
+    }
+
+    pub fn is_final(&self, block: u32) -> bool {
+        block <= self.finalized()
+    }
+
+    /// Whether to hold back the result of a block until the block is final. A
+    /// re-checked result is never held again; it is counted as persisted or resolved.
+    pub fn hold(&self, block: u32, recheck: bool, result: &TestResult) -> bool {
+        let matched = match result {
+            TestResult::Match { .. } => true,
+            TestResult::BothError {
+                rust_error,
+                sidecar_error,
+            } => rust_error.same_as(sidecar_error),
+            _ => false,
+        };
+        if recheck {
+            if !matched {
+                self.persisted.fetch_add(1, Ordering::Relaxed);
+            }
+            return false;
+        }
+        if matched || self.is_final(block) || self.gave_up.load(Ordering::Relaxed) {
+            return false;
+        }
+        self.held.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Wait until both servers have finalized `block`, polling their heads.
+    /// Returns false when the timeout passed first.
+    pub async fn wait_for(&self, client: &ApiClient, block: u32) -> bool {
+        if self.is_final(block) {
+            return true;
+        }
+        println!(
+            "\nWaiting for both servers to finalize #{} (finalized: #{}) to re-check held results...",
+            block,
+            self.finalized()
+        );
+        let started = Instant::now();
+        while !self.is_final(block) {
+            if started.elapsed() >= self.timeout {
+                self.gave_up.store(true, Ordering::Relaxed);
+                eprintln!(
+                    "Warning: #{} was not finalized within {}s (finalized: #{}); re-checking anyway",
+                    block,
+                    self.timeout.as_secs(),
+                    self.finalized()
+                );
+                return false;
+            }
+            tokio::time::sleep(FINALITY_POLL_INTERVAL).await;
+            let (rust, sidecar) = tokio::join!(
+                get_finalized_head(client, Server::Rust, &self.rust_url),
+                get_finalized_head(client, Server::Sidecar, &self.sidecar_url)
+            );
+            if let (Ok(rust), Ok(sidecar)) = (rust, sidecar) {
+                self.finalized
+                    .fetch_max(rust.number.min(sidecar.number), Ordering::Relaxed);
+            }
+        }
+        println!("Finalized: #{}", self.finalized());
+        true
+    }
+
+    /// One-line statistics, e.g. "4 results held back and re-checked: 1 persisted, 3 resolved"
+    pub fn summary(&self) -> String {
+        let held = self.held.load(Ordering::Relaxed);
+        let persisted = self.persisted.load(Ordering::Relaxed);
+        let mut summary = format!(
+            "{} results of unfinalized blocks held back and re-checked: {} persisted, {} resolved",
+            held,
+            persisted,
+            held.saturating_sub(persisted)
+        );
+        if self.gave_up.load(Ordering::Relaxed) {
+            summary.push_str(
+                " (finality timed out; some blocks were re-checked before they were final)",
+            );
+        }
+        summary
+    }
+}
@@ -0,0 +1,101 @@
+//! Per-server request limits.
+//!
+//! Scanners keep a window of `--concurrency` request pairs in flight and start the
+//! next pair as soon as any pair completes, so one slow block no longer holds up
+//! the rest. On top of that window, each server has its own limits: a maximum
+//! number of concurrent requests and an optional requests-per-second rate enforced
+//! by a token bucket. Sidecar usually saturates far earlier than the Rust API, so
+//! it can be held back without slowing down requests to the Rust API. The limits
+//! apply to every attempt, including retries, but not to the wait between retries.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Limits for the requests sent to one server
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ServerLimits {
+    /// Maximum concurrent requests (None = bounded only by the scan window)
+    pub concurrency: Option<usize>,
+    /// Maximum requests per second (None = unlimited)
+    pub rate: Option<f64>,
+}
+
+impl fmt::Display for ServerLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.concurrency, self.rate) {
+            (None, None) => write!(f, "unlimited"),
+            (Some(concurrency), None) => write!(f, "{} concurrent", concurrency),
+            (None, Some(rate)) => write!(f, "{} req/s", rate),
+            (Some(concurrency), Some(rate)) => {
+                write!(f, "{} concurrent, {} req/s", concurrency, rate)
+            }
+        }
+    }
+}
+
+/// Enforces the `ServerLimits` of one server across all requests of a run
+#[derive(Debug)]
+pub struct Throttle {
+    permits: Option<Semaphore>,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+impl Throttle {
+    pub fn new(limits: ServerLimits) -> Self {
+        Throttle {
+            permits: limits.concurrency.map(|n| Semaphore::new(n.max(1))),
+            bucket: limits.rate.map(|rate| Mutex::new(TokenBucket::new(rate))),
+        }
+    }
+
+    /// Wait for a concurrency slot, then for a rate token. The slot is held until
+    /// the returned permit is dropped.
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match self.permits {
+            Some(ref permits) => Some(permits.acquire().await.expect("semaphore is never closed")),
+            None => None,
+        };
+        if let Some(ref bucket) = self.bucket {
+            let wait = bucket.lock().unwrap().reserve();
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+        }
+        permit
+    }
+}
+
+/// Token bucket holding at most one token, so requests are spaced evenly at
+/// `1 / rate` instead of being sent in bursts. Waiting requests reserve their
+/// token up front (the balance goes negative) and are served in arrival order.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        TokenBucket {
+            rate,
+            tokens: 1.0,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Take one token and return how long to wait until it is available
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.updated).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(1.0) - 1.0;
+        self.updated = now;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
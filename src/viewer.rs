@@ -0,0 +1,344 @@
+//! Side-by-side terminal diff viewer for a single response pair.
+//!
+//! Both responses are walked together and rendered as aligned tree rows: Rust on
+//! the left, Sidecar on the right. Rows are classified and arrays aligned with the
+//! scan's rules, so the tree agrees with the diff list printed under it. Changed
+//! values are highlighted, values equal only under the rules are marked `≈`, fields
+//! present on one side only are colored by side, equal subtrees are collapsed to a
+//! single row, and runs of equal rows far from any change are elided, leaving
+//! `context` rows around each change.
+
+use serde_json::Value;
+use std::io::IsTerminal;
+
+use crate::diff::{align_arrays, format_pointer, json_diff, json_diff_at, JsonDiff, PathSegment};
+use crate::rules::DiffRules;
+
+/// Column width used when the terminal width is unknown
+const DEFAULT_WIDTH: usize = 160;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const DIM: &str = "\x1b[2m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// How the two sides of a row relate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowKind {
+    Equal,
+    /// Equal only under the rules (equivalent diffs)
+    Equivalent,
+    Changed,
+    RustOnly,
+    SidecarOnly,
+}
+
+/// One aligned line of the side-by-side view
+#[derive(Debug)]
+struct Row {
+    depth: usize,
+    rust: Option<String>,
+    sidecar: Option<String>,
+    kind: RowKind,
+}
+
+/// Viewer settings
+#[derive(Debug, Clone, Copy)]
+pub struct ViewOptions {
+    /// Equal rows shown around each change
+    pub context: usize,
+    /// Expand equal subtrees instead of collapsing them to one row
+    pub expand_equal: bool,
+}
+
+/// Render the side-by-side diff of two responses, followed by the diff list
+/// computed with the scan's rules
+pub fn show_diff(
+    rust_label: &str,
+    sidecar_label: &str,
+    rust: &Value,
+    sidecar: &Value,
+    rules: &DiffRules,
+    options: ViewOptions,
+) {
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let width = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_WIDTH)
+        .max(40);
+    let column = (width - 3) / 2;
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut rows = Vec::new();
+    push_rows(
+        ("", ""),
+        Some(rust),
+        Some(sidecar),
+        &mut Vec::new(),
+        &[],
+        rules,
+        options.expand_equal,
+        &mut rows,
+    );
+
+    println!(
+        "{} │ {}",
+        paint(
+            BOLD,
+            &pad(&clip(&format!("Rust: {}", rust_label), column), column)
+        ),
+        paint(BOLD, &clip(&format!("Sidecar: {}", sidecar_label), column))
+    );
+    println!("{}┼{}", "─".repeat(column + 1), "─".repeat(column + 1));
+
+    let visible = visible_rows(&rows, options.context);
+    let mut i = 0;
+    while i < rows.len() {
+        if !visible[i] {
+            let hidden = visible[i..].iter().take_while(|v| !**v).count();
+            println!("{}", paint(DIM, &format!("  ⋮ {} equal line(s)", hidden)));
+            i += hidden;
+            continue;
+        }
+        let row = &rows[i];
+        let indent = "  ".repeat(row.depth);
+        let side = |text: &Option<String>| match text {
+            Some(text) => clip(&format!("{}{}", indent, text), column),
+            None => String::new(),
+        };
+        let (left, right) = (pad(&side(&row.rust), column), side(&row.sidecar));
+        let (left, marker, right) = match row.kind {
+            RowKind::Equal => (left, " │ ", right),
+            RowKind::Equivalent => (paint(DIM, &left), " ≈ ", paint(DIM, &right)),
+            RowKind::Changed => (paint(YELLOW, &left), " ≠ ", paint(YELLOW, &right)),
+            RowKind::RustOnly => (paint(RED, &left), " < ", right),
+            RowKind::SidecarOnly => (left, " > ", paint(GREEN, &right)),
+        };
+        println!("{}{}{}", left, marker, right.trim_end());
+        i += 1;
+    }
+
+    let outcome = json_diff(rust, sidecar, rules);
+    let total = outcome.diffs.len() + outcome.omitted;
+    println!();
+    if total == 0 {
+        println!("No differences.");
+        return;
+    }
+    println!(
+        "{} difference(s), {} not equivalent:",
+        total, outcome.hard_diffs
+    );
+    for diff in &outcome.diffs {
+        let line = format!("  - {}", diff);
+        if diff.diff_type.is_equivalent() {
+            println!("{}", paint(DIM, &line));
+        } else {
+            println!("{}", line);
+        }
+    }
+    if outcome.omitted > 0 {
+        println!("  ... and {} more", outcome.omitted);
+    }
+}
+
+/// Append the rows for one field at `path`. `labels` are its Rust and Sidecar
+/// labels (`"key": `, `[index] ` or empty at the root). A field present on one
+/// side only is a change if `parent_diffs`, the diffs of the enclosing value,
+/// report it (the rules may ignore it or treat `null` as missing).
+fn push_rows(
+    labels: (&str, &str),
+    rust: Option<&Value>,
+    sidecar: Option<&Value>,
+    path: &mut Vec<PathSegment>,
+    parent_diffs: &[JsonDiff],
+    rules: &DiffRules,
+    expand_equal: bool,
+    rows: &mut Vec<Row>,
+) {
+    let depth = path.len();
+    let row = |rust: Option<String>, sidecar: Option<String>, kind| Row {
+        depth,
+        rust,
+        sidecar,
+        kind,
+    };
+    let (rust_label, sidecar_label) = labels;
+    let (r, s) = match (rust, sidecar) {
+        (Some(r), Some(s)) => (r, s),
+        (Some(r), None) => {
+            let reported = parent_diffs
+                .iter()
+                .any(|d| d.pointer == format_pointer(path));
+            let kind = if reported {
+                RowKind::RustOnly
+            } else {
+                RowKind::Equal
+            };
+            rows.push(row(Some(format!("{}{}", rust_label, r)), None, kind));
+            return;
+        }
+        (None, Some(s)) => {
+            let reported = parent_diffs
+                .iter()
+                .any(|d| d.pointer == format_pointer(path));
+            let kind = if reported {
+                RowKind::SidecarOnly
+            } else {
+                RowKind::Equal
+            };
+            rows.push(row(None, Some(format!("{}{}", sidecar_label, s)), kind));
+            return;
+        }
+        (None, None) => return,
+    };
+
+    let outcome = json_diff_at(r, s, path, rules);
+    let kind = if outcome.hard_diffs > 0 {
+        RowKind::Changed
+    } else if outcome.diffs.is_empty() && outcome.omitted == 0 {
+        RowKind::Equal
+    } else {
+        RowKind::Equivalent
+    };
+    if kind == RowKind::Equal && !(expand_equal && is_container(r)) {
+        rows.push(row(
+            Some(format!("{}{}", rust_label, summary(r))),
+            Some(format!("{}{}", sidecar_label, summary(s))),
+            RowKind::Equal,
+        ));
+        return;
+    }
+
+    // Diffs of the container itself, e.g. an array length or order mismatch
+    let pointer = format_pointer(path);
+    let own_kind = match outcome.diffs.iter().find(|d| d.pointer == pointer) {
+        Some(d) if !d.diff_type.is_equivalent() => RowKind::Changed,
+        Some(_) => RowKind::Equivalent,
+        None => RowKind::Equal,
+    };
+    let open = |bracket: &str| {
+        row(
+            Some(format!("{}{}", rust_label, bracket)),
+            Some(format!("{}{}", sidecar_label, bracket)),
+            own_kind,
+        )
+    };
+    match (r, s) {
+        (Value::Object(r), Value::Object(s)) => {
+            rows.push(open("{"));
+            for (key, r_val) in r {
+                let label = format!("\"{}\": ", key);
+                path.push(PathSegment::Key(key.clone()));
+                push_rows(
+                    (&label, &label),
+                    Some(r_val),
+                    s.get(key),
+                    path,
+                    &outcome.diffs,
+                    rules,
+                    expand_equal,
+                    rows,
+                );
+                path.pop();
+            }
+            for (key, s_val) in s.iter().filter(|(k, _)| !r.contains_key(*k)) {
+                let label = format!("\"{}\": ", key);
+                path.push(PathSegment::Key(key.clone()));
+                push_rows(
+                    (&label, &label),
+                    None,
+                    Some(s_val),
+                    path,
+                    &outcome.diffs,
+                    rules,
+                    expand_equal,
+                    rows,
+                );
+                path.pop();
+            }
+            rows.push(row(Some("}".into()), Some("}".into()), RowKind::Equal));
+        }
+        (Value::Array(r), Value::Array(s)) => {
+            rows.push(open("["));
+            // Paired and rust-only elements are addressed by rust index, like their diffs
+            for (i, j) in align_arrays(r, s, path, rules) {
+                let label =
+                    |index: Option<usize>| index.map_or(String::new(), |k| format!("[{}] ", k));
+                let Some(index) = i.or(j) else {
+                    continue;
+                };
+                path.push(PathSegment::Index(index));
+                push_rows(
+                    (&label(i), &label(j)),
+                    i.map(|i| &r[i]),
+                    j.map(|j| &s[j]),
+                    path,
+                    &outcome.diffs,
+                    rules,
+                    expand_equal,
+                    rows,
+                );
+                path.pop();
+            }
+            rows.push(row(Some("]".into()), Some("]".into()), RowKind::Equal));
+        }
+        _ => rows.push(row(
+            Some(format!("{}{}", rust_label, r)),
+            Some(format!("{}{}", sidecar_label, s)),
+            kind,
+        )),
+    }
+}
+
+/// Which rows to print: every change plus `context` rows on either side
+fn visible_rows(rows: &[Row], context: usize) -> Vec<bool> {
+    let mut visible = vec![false; rows.len()];
+    for (i, row) in rows.iter().enumerate() {
+        if row.kind != RowKind::Equal {
+            let start = i.saturating_sub(context);
+            let end = (i + context + 1).min(rows.len());
+            visible[start..end].iter_mut().for_each(|v| *v = true);
+        }
+    }
+    visible
+}
+
+/// Whether a value is a non-empty object or array
+fn is_container(v: &Value) -> bool {
+    matches!(v, Value::Object(o) if !o.is_empty()) || matches!(v, Value::Array(a) if !a.is_empty())
+}
+
+/// One-line rendering of an equal value; containers collapse to their size
+fn summary(v: &Value) -> String {
+    match v {
+        Value::Object(o) if !o.is_empty() => format!("{{…}} ({} fields)", o.len()),
+        Value::Array(a) if !a.is_empty() => format!("[…] ({} items)", a.len()),
+        _ => v.to_string(),
+    }
+}
+
+/// Cut text to `width` characters, marking the cut with `…`
+fn clip(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(width.saturating_sub(1)).collect();
+    clipped.push('…');
+    clipped
+}
+
+/// Right-pad text with spaces to `width` characters
+fn pad(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    format!("{}{}", text, " ".repeat(width.saturating_sub(len)))
+}